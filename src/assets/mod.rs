@@ -0,0 +1,382 @@
+use crate::{audio::{Sound, Trigger}, engine::Font, ui::Layout, world::{EntityId, World}};
+#[cfg(feature = "rhai")]
+use crate::world::{scripting, Script};
+
+pub mod tiled;
+pub mod pack;
+pub mod source;
+#[cfg(feature = "native")]
+pub mod validate;
+#[cfg(feature = "native")]
+pub mod watch;
+
+#[cfg(feature = "native")]
+use futures::executor::block_on;
+#[cfg(not(feature = "native"))]
+use futures::stream::{FuturesUnordered, StreamExt};
+use image::RgbaImage;
+use serde::{Serialize, Deserialize};
+use std::{fmt, error::Error};
+
+pub use source::AssetSource;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+enum AssetType {
+    Texture,
+    /// Level made in the Tiled editor, exported as JSON
+    TiledMap,
+    /// Rhai script, attached to the player or the entities named `entity_id`
+    #[cfg(feature = "rhai")]
+    Script,
+    /// WAV or Ogg Vorbis sound, played on its `trigger`
+    Sound,
+    /// Bitmap font as a BMFont text file, its page image next to it
+    Font,
+    /// UI widgets as JSON, see `ui::Layout`, with icon images relative to it
+    Layout,
+    Unknown
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Asset {
+    path: String,
+    r#type: AssetType,
+    entity_id: Option<String>,
+    /// Gameplay event playing a `Sound` asset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    trigger: Option<Trigger>,
+}
+
+#[derive(Debug)]
+struct AssetImportError {
+    asset: String
+}
+impl fmt::Display for AssetImportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not import asset: {}", self.asset)
+    }
+}
+impl Error for AssetImportError {}
+
+/// Loose asset files, relative to the working directory or website root
+#[cfg(feature = "native")]
+pub const ASSET_BASE_PATH: &str = "./assets";
+
+#[cfg(target_arch = "wasm32")]
+pub const ASSET_BASE_PATH: &str = "/assets";
+
+/// Made by the `assetpack` binary, used instead of `ASSET_BASE_PATH` if it exists
+#[cfg(feature = "native")]
+pub const PACK_PATH: &str = "./assets.pack";
+
+#[cfg(target_arch = "wasm32")]
+pub const PACK_PATH: &str = "/assets.pack";
+
+/// How far `import_assets_with_progress()` got
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Progress {
+    pub loaded_items: usize,
+    pub total_items: usize,
+    pub loaded_bytes: u64,
+    /// Grows when maps turn out to need tilesets, zero if the source cannot tell sizes
+    pub total_bytes: u64,
+}
+
+impl Progress {
+    /// Between 0 and 1, by bytes if the sizes are known and by items otherwise
+    pub fn fraction(&self) -> f64 {
+        if self.total_bytes > 0 {
+            (self.loaded_bytes as f64 / self.total_bytes as f64).min(1.)
+        } else if self.total_items > 0 {
+            self.loaded_items as f64 / self.total_items as f64
+        } else {
+            1.
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.loaded_items == self.total_items
+    }
+}
+
+/// An asset read and decoded, ready to be added to a world
+enum Loaded {
+    Texture(RgbaImage),
+    TiledMap(tiled::TiledMap, Vec<Option<RgbaImage>>),
+    #[cfg(feature = "rhai")]
+    Script(rhai::AST),
+    Sound(Sound),
+    Font(Font),
+    Layout(Layout, Vec<(String, RgbaImage)>),
+}
+
+/// Read a file, adding its size to `bytes`
+async fn read(source: &AssetSource, path: &str, bytes: &mut u64) -> Result<Vec<u8>, Box<dyn Error>> {
+    let data = source.read(path).await?;
+    *bytes += data.len() as u64;
+    Ok(data)
+}
+
+async fn load_texture(source: &AssetSource, path: &str, bytes: &mut u64) -> Result<RgbaImage, Box<dyn Error>> {
+    let data = read(source, path, bytes).await?;
+    Ok(image::load_from_memory(&data)?.to_rgba8())
+}
+
+/// Tiled map with its tileset images
+async fn load_map(source: &AssetSource, path: &str, bytes: &mut u64) -> Result<(tiled::TiledMap, Vec<Option<RgbaImage>>), Box<dyn Error>> {
+    let mut map = tiled::TiledMap::from_slice(&read(source, path, bytes).await?)?;
+    map.relative_to(path);
+
+    let mut images = Vec::new();
+    for image in map.tileset_images() {
+        match image {
+            Some(image) => images.push(Some(load_texture(source, image, bytes).await?)),
+            None => images.push(None),
+        }
+    }
+    Ok((map, images))
+}
+
+async fn load(source: &AssetSource, asset: &Asset, bytes: &mut u64) -> Result<Loaded, Box<dyn Error>> {
+    match asset.r#type {
+        AssetType::Texture => Ok(Loaded::Texture(load_texture(source, &asset.path, bytes).await?)),
+        AssetType::TiledMap => {
+            let (map, images) = load_map(source, &asset.path, bytes).await?;
+            Ok(Loaded::TiledMap(map, images))
+        },
+        #[cfg(feature = "rhai")]
+        AssetType::Script => {
+            let text = String::from_utf8(read(source, &asset.path, bytes).await?)?;
+            Ok(Loaded::Script(scripting::compile(&text)?))
+        },
+        AssetType::Sound => Ok(Loaded::Sound(Sound::decode(&read(source, &asset.path, bytes).await?)?)),
+        AssetType::Font => {
+            let descriptor = String::from_utf8(read(source, &asset.path, bytes).await?)?;
+            let page = Font::bmfont_page(&descriptor)
+                .ok_or_else(|| Box::new(AssetImportError { asset: source.locate(&asset.path) }))?;
+            let page = load_texture(source, &relative_path(&asset.path, page), bytes).await?;
+            Ok(Loaded::Font(Font::from_bmfont(&descriptor, &page)?))
+        },
+        AssetType::Layout => {
+            let mut layout = Layout::from_slice(&read(source, &asset.path, bytes).await?)?;
+            layout.relative_to(&asset.path);
+            let mut images = Vec::new();
+            for image in layout.images() {
+                images.push((image.to_string(), load_texture(source, image, bytes).await?));
+            }
+            Ok(Loaded::Layout(layout, images))
+        },
+        AssetType::Unknown => Err(Box::new(AssetImportError { asset: source.locate(&asset.path) })),
+    }
+}
+
+fn apply(source: &AssetSource, asset: &Asset, loaded: Loaded, world: &mut World) -> Result<(), Box<dyn Error>> {
+    match loaded {
+        Loaded::Texture(texture) => {
+            if let Some(id) = &asset.entity_id {
+                if id == "player" {
+                    let players: Vec<EntityId> = world.components.player.ids().collect();
+                    for player in players {
+                        world.set_texture(player, Some(texture.clone()), Some(asset.path.clone()));
+                    }
+                    return Ok(())
+                }
+            }
+        },
+        Loaded::TiledMap(map, images) => return map.apply(world, images),
+        #[cfg(feature = "rhai")]
+        Loaded::Script(ast) => {
+            world.scripts.insert(asset.path.clone(), std::sync::Arc::new(ast));
+
+            if let Some(name) = &asset.entity_id {
+                let c = &world.components;
+                let targets: Vec<EntityId> = if name == "player" {
+                    c.player.ids().collect()
+                } else {
+                    c.properties.iter()
+                        .filter(|(_, p)| p.get("name") == Some(name))
+                        .map(|(id, _)| id)
+                        .collect()
+                };
+                for id in targets {
+                    world.components.script.insert(id, Script::new(asset.path.clone()));
+                }
+            }
+            return Ok(())
+        },
+        Loaded::Sound(sound) => {
+            let sounds = &mut world.sounds;
+            sounds.clips.insert(asset.path.clone(), std::sync::Arc::new(sound));
+            if let Some(trigger) = asset.trigger {
+                sounds.triggers.insert(trigger, asset.path.clone());
+            }
+            return Ok(())
+        },
+        Loaded::Font(font) => {
+            world.fonts.insert(asset.path.clone(), std::sync::Arc::new(font));
+            return Ok(())
+        },
+        Loaded::Layout(layout, images) => {
+            for (path, image) in images {
+                world.ui.images.insert(path, std::sync::Arc::new(image));
+            }
+            world.ui.set_layout(layout);
+            return Ok(())
+        },
+    }
+    Err(Box::new(AssetImportError { asset: source.locate(&asset.path) }))
+}
+
+/// Path of `file`, given relative to the directory of the asset at `path`
+fn relative_path(path: &str, file: &str) -> String {
+    match path.rfind('/') {
+        Some(i) => format!("{}/{}", &path[..i], file),
+        None => file.to_string(),
+    }
+}
+
+pub async fn import_asset(source: &AssetSource, asset: &Asset, world: &mut World) -> Result<(), Box<dyn Error>> {
+    let loaded = load(source, asset, &mut 0).await?;
+    apply(source, asset, loaded, world)
+}
+
+pub async fn import_assets(source: &AssetSource, assets: Vec<Asset>, world: &mut World) -> Result<(), Box<dyn Error>> {
+    import_assets_with_progress(source, assets, world, |_| {}).await
+}
+
+/// Load every asset on worker threads, calling `on_loaded` on this thread as each one is done
+///
+/// Reading files never waits on native, so futures alone would load one asset after another.
+#[cfg(feature = "native")]
+fn load_on_threads(
+    source: &AssetSource,
+    assets: &[Asset],
+    mut on_loaded: impl FnMut(usize, Result<Loaded, Box<dyn Error>>, u64),
+) {
+    use std::{sync::{atomic::{AtomicUsize, Ordering}, mpsc}, thread};
+
+    let next = AtomicUsize::new(0);
+    let workers = thread::available_parallelism().map_or(1, |n| n.get()).min(assets.len());
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..workers {
+            let (sender, next) = (sender.clone(), &next);
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let asset = match assets.get(i) {
+                    Some(asset) => asset,
+                    None => return,
+                };
+                let mut bytes = 0;
+                // Errors are not `Send`, only their message goes across
+                let loaded = block_on(load(source, asset, &mut bytes)).map_err(|e| e.to_string());
+                if sender.send((i, loaded, bytes)).is_err() {
+                    return;
+                }
+            });
+        }
+        drop(sender);
+        for (i, loaded, bytes) in receiver {
+            on_loaded(i, loaded.map_err(Into::into), bytes);
+        }
+    });
+}
+
+/// Load every asset at the same time, calling `on_progress` after each one
+///
+/// Native loads on worker threads, the web on overlapping fetches. Assets are added to
+/// `world` in index order once they are all loaded, so scripts can still be attached to the
+/// objects of maps before them.
+pub async fn import_assets_with_progress(
+    source: &AssetSource,
+    assets: Vec<Asset>,
+    world: &mut World,
+    mut on_progress: impl FnMut(Progress),
+) -> Result<(), Box<dyn Error>> {
+    let sizes: Option<Vec<u64>> = assets.iter().map(|asset| source.size(&asset.path)).collect();
+    let mut progress = Progress {
+        total_items: assets.len(),
+        total_bytes: sizes.as_ref().map_or(0, |sizes| sizes.iter().sum()),
+        ..Progress::default()
+    };
+    on_progress(progress);
+
+    let mut loaded: Vec<Option<Loaded>> = assets.iter().map(|_| None).collect();
+    let mut error = None;
+    let mut on_loaded = |i: usize, result: Result<Loaded, Box<dyn Error>>, bytes: u64| {
+        match result {
+            Ok(result) => loaded[i] = Some(result),
+            Err(e) => {
+                error.get_or_insert(e);
+                return;
+            }
+        }
+        progress.loaded_items += 1;
+        progress.loaded_bytes += bytes;
+        if let Some(sizes) = &sizes {
+            // Tilesets were not counted before
+            progress.total_bytes = (progress.total_bytes + bytes).saturating_sub(sizes[i]);
+        }
+        on_progress(progress);
+    };
+
+    #[cfg(feature = "native")]
+    load_on_threads(source, &assets, &mut on_loaded);
+
+    #[cfg(not(feature = "native"))]
+    {
+        let mut pending: FuturesUnordered<_> = assets
+            .iter()
+            .enumerate()
+            .map(|(i, asset)| async move {
+                let mut bytes = 0;
+                let loaded = load(source, asset, &mut bytes).await;
+                (i, loaded, bytes)
+            })
+            .collect();
+        while let Some((i, result, bytes)) = pending.next().await {
+            on_loaded(i, result, bytes);
+        }
+    }
+
+    if let Some(e) = error {
+        return Err(e);
+    }
+
+    for (asset, loaded) in assets.iter().zip(loaded) {
+        if let Some(loaded) = loaded {
+            apply(source, asset, loaded, world)?;
+        }
+    }
+    Ok(())
+}
+
+/// Import an asset again after it changed, keeping the state of the entities it affects
+pub async fn reload_asset(source: &AssetSource, asset: &Asset, world: &mut World) -> Result<(), Box<dyn Error>> {
+    match asset.r#type {
+        AssetType::TiledMap => {
+            let (map, images) = load_map(source, &asset.path, &mut 0).await?;
+            map.reload(world, images)
+        },
+        _ => import_asset(source, asset, world).await,
+    }
+}
+
+/// Read the asset index from `source`
+pub async fn load_assets(source: &AssetSource) -> Result<Vec<Asset>, Box<dyn Error>> {
+    let index: Vec<Asset> = match serde_json::from_slice(&source.read("index.json").await?) {
+        Ok(index) => index,
+        // The web server may not have the index, but it is bundled with the page
+        #[cfg(target_arch = "wasm32")]
+        Err(_) if matches!(source, AssetSource::Fetch(_)) => {
+            serde_json::from_slice(&crate::wasm_utils::load_asset_index().await?)?
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    #[cfg(feature = "native")]
+    println!("Loaded asset index: {:#?}", index);
+    #[cfg(target_arch = "wasm32")]
+    crate::log!("Loaded asset index: {:#?}", index);
+    Ok(index)
+}
@@ -0,0 +1,164 @@
+//! Every asset file in a single compressed archive
+//!
+//! Layout, integers are little endian:
+//!
+//! | bytes | content                                  |
+//! |-------|------------------------------------------|
+//! | 4     | `MAGIC`                                  |
+//! | 4     | `PACK_VERSION`                           |
+//! | 4     | length of the index                      |
+//! | ...   | index, JSON array of `PackEntry`         |
+//! | ...   | deflate compressed files, back to back   |
+
+use serde::{Serialize, Deserialize};
+use std::{collections::BTreeMap, fmt, error::Error};
+
+#[cfg(feature = "native")]
+use std::{fs, path::Path};
+
+pub const MAGIC: &[u8; 4] = b"CGPK";
+pub const PACK_VERSION: u32 = 1;
+
+const COMPRESSION_LEVEL: u8 = 9;
+
+/// Deflate cannot expand data by more than this factor
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+#[derive(Debug)]
+pub struct PackError {
+    reason: String,
+}
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid asset pack: {}", self.reason)
+    }
+}
+impl Error for PackError {}
+
+fn invalid(reason: impl Into<String>) -> Box<dyn Error> {
+    Box::new(PackError { reason: reason.into() })
+}
+
+/// Where a file is in the pack
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct PackEntry {
+    /// Asset path, relative to the asset root
+    pub path: String,
+    /// Start of the compressed data, counted from the end of the index
+    pub offset: u64,
+    /// Size of the compressed data
+    pub size: u64,
+    /// Size of the file once decompressed
+    pub length: u64,
+}
+
+pub struct AssetPack {
+    entries: BTreeMap<String, PackEntry>,
+    blobs: Vec<u8>,
+}
+
+impl AssetPack {
+    /// Compress `files` (path, content) into a pack
+    pub fn build<'a>(files: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut blobs = Vec::new();
+        for (path, data) in files {
+            let compressed = miniz_oxide::deflate::compress_to_vec(data, COMPRESSION_LEVEL);
+            index.push(PackEntry {
+                path: path.to_string(),
+                offset: blobs.len() as u64,
+                size: compressed.len() as u64,
+                length: data.len() as u64,
+            });
+            blobs.extend_from_slice(&compressed);
+        }
+
+        let index = serde_json::to_vec(&index).unwrap_or_default();
+        let mut pack = Vec::with_capacity(12 + index.len() + blobs.len());
+        pack.extend_from_slice(MAGIC);
+        pack.extend_from_slice(&PACK_VERSION.to_le_bytes());
+        pack.extend_from_slice(&(index.len() as u32).to_le_bytes());
+        pack.extend_from_slice(&index);
+        pack.extend_from_slice(&blobs);
+        pack
+    }
+
+    pub fn from_bytes(mut data: Vec<u8>) -> Result<AssetPack, Box<dyn Error>> {
+        if data.len() < 12 || &data[..4] != MAGIC {
+            return Err(invalid("not an asset pack"));
+        }
+        let word = |at: usize| u32::from_le_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]]);
+        let (version, index_len) = (word(4), word(8) as usize);
+        if version != PACK_VERSION {
+            return Err(invalid(format!("unsupported version {}", version)));
+        }
+        let index_end = 12usize.checked_add(index_len).filter(|end| *end <= data.len())
+            .ok_or_else(|| invalid("index is cut off"))?;
+
+        let index: Vec<PackEntry> = serde_json::from_slice(&data[12..index_end])?;
+        let blobs = data.split_off(index_end);
+        for entry in index.iter() {
+            if entry.offset.checked_add(entry.size).is_none_or(|end| end > blobs.len() as u64) {
+                return Err(invalid(format!("{} is cut off", entry.path)));
+            }
+        }
+
+        Ok(AssetPack {
+            entries: index.into_iter().map(|e| (e.path.clone(), e)).collect(),
+            blobs,
+        })
+    }
+
+    /// Pack every file below `root`
+    #[cfg(feature = "native")]
+    pub fn from_dir(root: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut files = Vec::new();
+        let mut directories = vec![root.to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    directories.push(path);
+                    continue;
+                }
+                let relative = path.strip_prefix(root)?.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((relative, fs::read(&path)?));
+            }
+        }
+        files.sort();
+        Ok(Self::build(files.iter().map(|(path, data)| (path.as_str(), data.as_slice()))))
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &PackEntry> {
+        self.entries.values()
+    }
+
+    pub fn entry(&self, path: &str) -> Option<&PackEntry> {
+        self.entries.get(path)
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    /// Decompressed content of the file at `path`
+    pub fn read(&self, path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let entry = self.entries.get(path).ok_or_else(|| invalid(format!("{} is missing", path)))?;
+        let blob = &self.blobs[entry.offset as usize..(entry.offset + entry.size) as usize];
+        if entry.length > entry.size.saturating_mul(MAX_DEFLATE_RATIO) {
+            return Err(invalid(format!("{} claims more data than it can hold", path)));
+        }
+        // The buffer grows by doubling and gives up as soon as the next size is past the limit,
+        // so leave room for that, the exact length is checked below
+        let limit = (entry.length as usize).saturating_mul(2).saturating_add(1);
+        let data = miniz_oxide::inflate::decompress_to_vec_with_limit(blob, limit)
+            .map_err(|e| invalid(format!("{} does not decompress: {:?}", path, e)))?;
+        if data.len() as u64 != entry.length {
+            return Err(invalid(format!("{} has the wrong size", path)));
+        }
+        Ok(data)
+    }
+}
@@ -0,0 +1,173 @@
+//! Where asset files are read from, chosen when the game starts
+//!
+//! Asset paths are always relative to the source, like the paths in `index.json`.
+
+use super::{pack::AssetPack, ASSET_BASE_PATH, PACK_PATH};
+
+use std::{collections::BTreeMap, error::Error, fmt};
+
+#[cfg(feature = "native")]
+use std::{fs, path::{Path, PathBuf}};
+
+#[derive(Debug)]
+pub struct MissingAssetError {
+    path: String,
+}
+impl fmt::Display for MissingAssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Missing asset file: {}", self.path)
+    }
+}
+impl Error for MissingAssetError {}
+
+pub enum AssetSource {
+    /// Files below a directory
+    #[cfg(feature = "native")]
+    Directory(PathBuf),
+    /// Files fetched from the web server, below a base URL
+    #[cfg(target_arch = "wasm32")]
+    Fetch(String),
+    /// Files compiled into the binary, see `embedded_assets!`
+    Embedded(&'static [(&'static str, &'static [u8])]),
+    /// Files held in memory, mostly for tests
+    Memory(BTreeMap<String, Vec<u8>>),
+    /// Files in an asset pack made by the `assetpack` binary
+    Pack(AssetPack),
+}
+
+impl AssetSource {
+    #[cfg(feature = "native")]
+    pub fn directory(root: impl Into<PathBuf>) -> Self {
+        AssetSource::Directory(root.into())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn fetch(base: impl Into<String>) -> Self {
+        AssetSource::Fetch(base.into())
+    }
+
+    pub fn memory<'a>(files: impl IntoIterator<Item = (&'a str, Vec<u8>)>) -> Self {
+        AssetSource::Memory(files.into_iter().map(|(path, data)| (path.to_string(), data)).collect())
+    }
+
+    pub fn pack(data: Vec<u8>) -> Result<Self, Box<dyn Error>> {
+        Ok(AssetSource::Pack(AssetPack::from_bytes(data)?))
+    }
+
+    /// A directory, or an asset pack if `path` is a file
+    #[cfg(feature = "native")]
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        if path.is_dir() {
+            Ok(Self::directory(path))
+        } else {
+            Self::pack(fs::read(path)?)
+        }
+    }
+
+    /// The first asset pack or directory found in the working directory, or next to the
+    /// executable, preferring packs
+    #[cfg(feature = "native")]
+    pub fn find() -> Result<Self, Box<dyn Error>> {
+        let mut bases = vec![PathBuf::from(".")];
+        if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf)) {
+            bases.push(dir);
+        }
+        for base in bases {
+            for candidate in [PACK_PATH, ASSET_BASE_PATH].iter() {
+                let path = base.join(candidate);
+                if path.exists() {
+                    return Self::open(path);
+                }
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// The asset pack on the web server if there is one, loose files otherwise
+    #[cfg(target_arch = "wasm32")]
+    pub async fn find() -> Result<Self, Box<dyn Error>> {
+        match crate::wasm_utils::fetch(PACK_PATH).await {
+            Ok(data) => Self::pack(data),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Content of the file at `path`
+    pub async fn read(&self, path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let missing = || -> Box<dyn Error> { Box::new(MissingAssetError { path: self.locate(path) }) };
+        match self {
+            #[cfg(feature = "native")]
+            AssetSource::Directory(root) => fs::read(root.join(path)).map_err(|_| missing()),
+            #[cfg(target_arch = "wasm32")]
+            AssetSource::Fetch(_) => crate::wasm_utils::fetch(&self.locate(path)).await,
+            AssetSource::Embedded(files) => files
+                .iter()
+                .find(|(p, _)| *p == path)
+                .map(|(_, data)| data.to_vec())
+                .ok_or_else(missing),
+            AssetSource::Memory(files) => files.get(path).cloned().ok_or_else(missing),
+            AssetSource::Pack(pack) => pack.read(path),
+        }
+    }
+
+    /// Size of the file at `path` without reading it, if the source can tell
+    pub fn size(&self, path: &str) -> Option<u64> {
+        match self {
+            #[cfg(feature = "native")]
+            AssetSource::Directory(root) => fs::metadata(root.join(path)).ok().map(|m| m.len()),
+            #[cfg(target_arch = "wasm32")]
+            AssetSource::Fetch(_) => None,
+            AssetSource::Embedded(files) => files.iter().find(|(p, _)| *p == path).map(|(_, data)| data.len() as u64),
+            AssetSource::Memory(files) => files.get(path).map(|data| data.len() as u64),
+            AssetSource::Pack(pack) => pack.entry(path).map(|entry| entry.length),
+        }
+    }
+
+    /// Where the file at `path` is, for messages
+    pub fn locate(&self, path: &str) -> String {
+        match self {
+            #[cfg(feature = "native")]
+            AssetSource::Directory(root) => root.join(path).display().to_string(),
+            #[cfg(target_arch = "wasm32")]
+            AssetSource::Fetch(base) => format!("{}/{}", base, path),
+            AssetSource::Pack(_) => format!("{}:{}", PACK_PATH, path),
+            _ => path.to_string(),
+        }
+    }
+
+    /// Directory holding the files, if they can change while the game runs
+    #[cfg(feature = "native")]
+    pub fn root(&self) -> Option<&Path> {
+        match self {
+            AssetSource::Directory(root) => Some(root),
+            _ => None,
+        }
+    }
+}
+
+impl Default for AssetSource {
+    /// Loose files in `ASSET_BASE_PATH`
+    fn default() -> Self {
+        #[cfg(feature = "native")]
+        { AssetSource::directory(ASSET_BASE_PATH) }
+
+        #[cfg(target_arch = "wasm32")]
+        { AssetSource::fetch(ASSET_BASE_PATH) }
+    }
+}
+
+/// `AssetSource` with files from a directory compiled into the binary
+///
+/// The directory is relative to the file using the macro, like for `include_bytes!`:
+///
+/// ```ignore
+/// let source = embedded_assets!("../assets", "index.json", "player.png");
+/// ```
+#[macro_export]
+macro_rules! embedded_assets {
+    ($dir:literal, $($path:literal),* $(,)?) => {{
+        const FILES: &[(&str, &[u8])] = &[$(($path, include_bytes!(concat!($dir, "/", $path)))),*];
+        $crate::assets::source::AssetSource::Embedded(FILES)
+    }};
+}
@@ -0,0 +1,397 @@
+//! Maps exported from the [Tiled](https://www.mapeditor.org) editor in JSON format
+//!
+//! Tile layers become `Tilemap`s, rectangle objects become entities and the map size
+//! becomes the world boundary. Tilesets have to be embedded in the map.
+
+use crate::world::{Ai, Collider, Coord, EntityId, Region, Tilemap, World};
+
+use image::RgbaImage;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, error::Error};
+
+/// Upper bits of a tile ID hold flipping flags
+const GID_MASK: u32 = 0x1fff_ffff;
+
+#[derive(Debug)]
+pub struct TiledMapError {
+    reason: String,
+}
+impl fmt::Display for TiledMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid Tiled map: {}", self.reason)
+    }
+}
+impl Error for TiledMapError {}
+
+fn invalid(reason: impl Into<String>) -> Box<dyn Error> {
+    Box::new(TiledMapError { reason: reason.into() })
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Property {
+    pub name: String,
+    pub value: serde_json::Value,
+}
+
+impl Property {
+    fn as_string(&self) -> String {
+        match &self.value {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Object {
+    /// Unique within the map, used to find the entity again when reloading
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, alias = "class")]
+    pub r#type: String,
+    pub x: f64,
+    pub y: f64,
+    #[serde(default)]
+    pub width: f64,
+    #[serde(default)]
+    pub height: f64,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Layer {
+    TileLayer {
+        #[serde(default)]
+        name: String,
+        width: usize,
+        height: usize,
+        data: serde_json::Value,
+        #[serde(default)]
+        encoding: Option<String>,
+        #[serde(default)]
+        offsetx: f64,
+        #[serde(default)]
+        offsety: f64,
+        #[serde(default)]
+        properties: Vec<Property>,
+    },
+    ObjectGroup {
+        #[serde(default)]
+        objects: Vec<Object>,
+    },
+    Group {
+        #[serde(default)]
+        layers: Vec<Layer>,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileProperties {
+    pub id: u32,
+    #[serde(default)]
+    pub properties: Vec<Property>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Tileset {
+    pub firstgid: u32,
+    /// Set for external tilesets, which are not supported
+    pub source: Option<String>,
+    pub image: Option<String>,
+    #[serde(default)]
+    pub tilecount: u32,
+    #[serde(default)]
+    pub tiles: Vec<TileProperties>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TiledMap {
+    pub width: usize,
+    pub height: usize,
+    pub tilewidth: u32,
+    pub tileheight: u32,
+    #[serde(default)]
+    pub infinite: bool,
+    pub layers: Vec<Layer>,
+    #[serde(default)]
+    pub tilesets: Vec<Tileset>,
+    /// Asset path of the map, set by `relative_to()`
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+/// Layers in drawing order, with groups expanded
+fn flatten<'a>(layers: &'a [Layer], out: &mut Vec<&'a Layer>) {
+    for layer in layers.iter() {
+        match layer {
+            Layer::Group { layers } => flatten(layers, out),
+            _ => out.push(layer),
+        }
+    }
+}
+
+fn is_true(properties: &[Property], name: &str) -> bool {
+    properties.iter().any(|p| p.name == name && p.value == serde_json::Value::Bool(true))
+}
+
+impl TiledMap {
+    pub fn from_slice(data: &[u8]) -> Result<TiledMap, Box<dyn Error>> {
+        let map: TiledMap = serde_json::from_slice(data)?;
+        if map.infinite {
+            return Err(invalid("infinite maps are not supported"));
+        }
+        if let Some(source) = map.tilesets.iter().find_map(|t| t.source.as_ref()) {
+            return Err(invalid(format!("external tileset {} has to be embedded", source)));
+        }
+        Ok(map)
+    }
+
+    /// Make tileset image paths relative to the asset root, instead of the map file at `path`
+    pub fn relative_to(&mut self, path: &str) {
+        self.source = Some(path.to_string());
+        if let Some(i) = path.rfind('/') {
+            for image in self.tilesets.iter_mut().filter_map(|t| t.image.as_mut()) {
+                *image = format!("{}/{}", &path[..i], image);
+            }
+        }
+    }
+
+    /// Images of the tilesets, in the order `apply()` expects them
+    pub fn tileset_images(&self) -> Vec<Option<&str>> {
+        self.tilesets.iter().map(|t| t.image.as_deref()).collect()
+    }
+
+    /// Every object of the map, in drawing order
+    pub fn objects(&self) -> Vec<&Object> {
+        let mut layers = Vec::new();
+        flatten(&self.layers, &mut layers);
+        layers.into_iter()
+            .filter_map(|layer| match layer {
+                Layer::ObjectGroup { objects } => Some(objects.iter()),
+                _ => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Size of the map relative to world origin
+    pub fn bounds(&self) -> Region {
+        let mut boundary = Region::default_boundary();
+        boundary.start.x = 0.;
+        boundary.start.y = 0.;
+        boundary.end.x = (self.width as u64 * self.tilewidth as u64) as f64;
+        boundary.end.y = (self.height as u64 * self.tileheight as u64) as f64;
+        boundary
+    }
+
+    /// Add the contents of the map to `world`
+    ///
+    /// `images` holds the decoded tileset images, in the order of `tileset_images()`.
+    pub fn apply(&self, world: &mut World, images: Vec<Option<RgbaImage>>) -> Result<(), Box<dyn Error>> {
+        let mut players: Vec<EntityId> = world.components.player.ids().collect();
+        players.reverse();
+        self.add_to(world, images, Some(&mut players))
+    }
+
+    /// Replace what an earlier `apply()` of this map added to `world`
+    ///
+    /// Players stay where they are, and entities keep their position and velocity if their
+    /// object is still in the map.
+    pub fn reload(&self, world: &mut World, images: Vec<Option<RgbaImage>>) -> Result<(), Box<dyn Error>> {
+        if self.source.is_none() {
+            return Err(invalid("only maps loaded with relative_to() can be reloaded"));
+        }
+
+        let mut previous = BTreeMap::new();
+        let ids: Vec<EntityId> = world.components.properties.iter()
+            .filter(|(_, p)| p.get("map") == self.source.as_ref())
+            .map(|(id, _)| id)
+            .collect();
+        for id in ids {
+            let object = world.components.properties.get(id).and_then(|p| p.get("object")).cloned();
+            let state = (world.components.position.get(id).copied(), world.components.velocity.get(id).copied());
+            if let Some(object) = object {
+                previous.insert(object, state);
+            }
+            world.despawn(id);
+        }
+
+        // New layers go where the old ones were
+        let at = world.tilemaps.iter().position(|t| t.source == self.source).unwrap_or(world.tilemaps.len());
+        let mut after = world.tilemaps.split_off(at);
+        after.retain(|t| t.source != self.source);
+
+        let existing: Vec<EntityId> = world.components.properties.ids().collect();
+        self.add_to(world, images, None)?;
+        world.tilemaps.extend(after);
+
+        let added: Vec<EntityId> = world.components.properties.ids().filter(|id| !existing.contains(id)).collect();
+        for id in added {
+            let object = world.components.properties.get(id).and_then(|p| p.get("object"));
+            if let Some((pos, vel)) = object.and_then(|o| previous.get(o)).copied() {
+                if let Some(pos) = pos {
+                    world.components.position.insert(id, pos);
+                }
+                if let Some(vel) = vel {
+                    world.components.velocity.insert(id, vel);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the map to `world`, with player objects moving `players` from the back,
+    /// or leaving the players alone without them
+    fn add_to(&self, world: &mut World, images: Vec<Option<RgbaImage>>, mut players: Option<&mut Vec<EntityId>>) -> Result<(), Box<dyn Error>> {
+        world.boundary = self.bounds();
+
+        let mut layers = Vec::new();
+        flatten(&self.layers, &mut layers);
+        for layer in layers {
+            match layer {
+                Layer::TileLayer { .. } => {
+                    world.tilemaps.extend(self.tile_layer(layer, &images)?);
+                }
+                Layer::ObjectGroup { objects } => {
+                    for object in objects.iter() {
+                        self.object(object, world, players.as_deref_mut());
+                    }
+                }
+                _ => {}
+            }
+        }
+        world.obstacles_changed();
+        Ok(())
+    }
+
+    /// One `Tilemap` per tileset used by the layer
+    fn tile_layer(&self, layer: &Layer, images: &[Option<RgbaImage>]) -> Result<Vec<Tilemap>, Box<dyn Error>> {
+        let (name, width, height, data, encoding, offset, properties) = match layer {
+            Layer::TileLayer { name, width, height, data, encoding, offsetx, offsety, properties } => {
+                (name, *width, *height, data, encoding, (*offsetx, *offsety), properties)
+            }
+            _ => return Ok(Vec::new()),
+        };
+        if encoding.as_deref().unwrap_or("csv") != "csv" {
+            return Err(invalid(format!("layer {} has to use CSV encoding", name)));
+        }
+        let gids: Vec<u32> = serde_json::from_value(data.clone())?;
+        let cells = width.checked_mul(height).ok_or_else(|| invalid(format!("layer {} is too big", name)))?;
+        if gids.len() != cells {
+            return Err(invalid(format!("layer {} has {} tiles instead of {}", name, gids.len(), cells)));
+        }
+        let all_solid = is_true(properties, "solid");
+
+        let mut tilemaps = Vec::new();
+        for (i, tileset) in self.tilesets.iter().enumerate() {
+            let end = tileset.firstgid.checked_add(tileset.tilecount)
+                .ok_or_else(|| invalid(format!("tileset {} has too many tiles", i)))?;
+            let range = tileset.firstgid..end;
+
+            let mut tilemap = Tilemap::new(width, height, (self.tilewidth, self.tileheight));
+            tilemap.origin = Coord { x: offset.0, y: offset.1, z: 0. };
+            let mut used = false;
+            for (cell, gid) in gids.iter().enumerate() {
+                let gid = gid & GID_MASK;
+                if range.contains(&gid) {
+                    tilemap.tiles[cell] = Some(gid - tileset.firstgid);
+                    used = true;
+                }
+            }
+            if !used {
+                continue;
+            }
+
+            tilemap.solid = vec![all_solid; tileset.tilecount as usize];
+            for tile in tileset.tiles.iter() {
+                if is_true(&tile.properties, "solid") {
+                    if let Some(solid) = tilemap.solid.get_mut(tile.id as usize) {
+                        *solid = true;
+                    }
+                }
+            }
+            tilemap.set_tileset(images.get(i).cloned().flatten());
+            tilemap.tileset_path = tileset.image.clone();
+            tilemap.source = self.source.clone();
+            tilemaps.push(tilemap);
+        }
+        Ok(tilemaps)
+    }
+
+    /// Objects of type `player` move the existing players in order or add new ones, unless
+    /// reloading. All others become entities, moving on their own if they have an `ai` or
+    /// `script` property.
+    fn object(&self, object: &Object, world: &mut World, players: Option<&mut Vec<EntityId>>) {
+        let (w, h) = (object.width / 2., object.height / 2.);
+        let pos = Coord { x: object.x + w, y: object.y + h, z: 0. };
+        if object.r#type == "player" {
+            match players.map(|p| p.pop()) {
+                Some(Some(id)) => { world.components.position.insert(id, pos); }
+                Some(None) => { world.spawn_player(pos); }
+                None => {}
+            }
+            return;
+        }
+
+        let id = world.spawn_body(pos);
+        let mut pos = pos;
+        let mut collider = Collider::new();
+        let mut speed = None;
+        #[cfg(feature = "rhai")]
+        let mut script = None;
+        if w > 0. && h > 0. {
+            collider.hitbox.start.x = -w;
+            collider.hitbox.start.y = -h;
+            collider.hitbox.end.x = w;
+            collider.hitbox.end.y = h;
+        }
+
+        let mut properties = BTreeMap::new();
+        if !object.name.is_empty() {
+            properties.insert("name".to_string(), object.name.clone());
+        }
+        if !object.r#type.is_empty() {
+            properties.insert("type".to_string(), object.r#type.clone());
+        }
+        if let Some(source) = &self.source {
+            properties.insert("map".to_string(), source.clone());
+            properties.insert("object".to_string(), object.id.to_string());
+        }
+        for property in object.properties.iter() {
+            match (property.name.as_str(), &property.value) {
+                ("solid", serde_json::Value::Bool(b)) => collider.solid = *b,
+                ("pushable", serde_json::Value::Bool(b)) => collider.pushable = *b,
+                ("mass", v) if v.is_number() => collider.mass = v.as_f64().unwrap_or(1.),
+                ("z", v) if v.is_number() => pos.z = v.as_f64().unwrap_or(0.),
+                ("height", v) if v.is_number() => collider.hitbox.start.z = v.as_f64().unwrap_or(0.),
+                ("speed", v) if v.is_number() => speed = v.as_f64(),
+                #[cfg(feature = "rhai")]
+                ("script", serde_json::Value::String(path)) => script = Some(path.clone()),
+                _ => {}
+            }
+            properties.insert(property.name.clone(), property.as_string());
+        }
+
+        let c = &mut world.components;
+        c.position.insert(id, pos);
+        c.collider.insert(id, collider);
+        if let (Some(speed), Some(vel)) = (speed, c.velocity.get_mut(id)) {
+            vel.max.x = speed;
+            vel.max.y = speed;
+        }
+        if let Some(ai) = Ai::from_properties(&properties) {
+            c.ai.insert(id, ai);
+        }
+        #[cfg(feature = "rhai")]
+        if let Some(path) = script {
+            c.script.insert(id, crate::world::Script::new(path));
+        }
+        c.properties.insert(id, properties);
+    }
+}
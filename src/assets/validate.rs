@@ -0,0 +1,198 @@
+//! Checking an asset directory before shipping it, instead of failing at startup
+
+use super::{tiled::TiledMap, Asset, AssetType};
+use crate::{audio::Sound, engine::Font, ui::Layout};
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+const INDEX: &str = "index.json";
+
+/// Findings about an asset directory
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Every file used, with its size in bytes
+    pub sizes: Vec<(String, u64)>,
+    /// Everything that would make importing fail, or do nothing
+    pub problems: Vec<String>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.sizes.iter().map(|(_, size)| size).sum()
+    }
+
+    fn problem(&mut self, path: &str, problem: impl std::fmt::Display) {
+        self.problems.push(format!("{}: {}", path, problem));
+    }
+
+    /// Content of `path` below `root`, noting its size
+    fn read(&mut self, root: &Path, path: &str) -> Option<Vec<u8>> {
+        match fs::read(root.join(path)) {
+            Ok(data) => {
+                if !self.sizes.iter().any(|(p, _)| p == path) {
+                    self.sizes.push((path.to_string(), data.len() as u64));
+                }
+                Some(data)
+            }
+            Err(e) => {
+                self.problem(path, e);
+                None
+            }
+        }
+    }
+
+    fn image(&mut self, root: &Path, path: &str) {
+        if let Some(data) = self.read(root, path) {
+            if let Err(e) = image::load_from_memory(&data) {
+                self.problem(path, e);
+            }
+        }
+    }
+}
+
+/// Check `index.json` below `root` and everything it refers to
+pub fn validate(root: &Path) -> Report {
+    let mut report = Report::default();
+    let index: Vec<Asset> = match report.read(root, INDEX).map(|data| serde_json::from_slice(&data)) {
+        Some(Ok(index)) => index,
+        Some(Err(e)) => {
+            report.problem(INDEX, e);
+            return report;
+        }
+        None => return report,
+    };
+
+    // Names of level objects, which scripts can be attached to
+    let mut names = BTreeSet::new();
+    let mut scripts = Vec::new();
+    for asset in index.iter() {
+        let path = asset.path.as_str();
+        match asset.r#type {
+            AssetType::Texture => {
+                report.image(root, path);
+                if asset.entity_id.as_deref() != Some("player") {
+                    report.problem(path, "textures can only be used with entity_id \"player\"");
+                }
+            }
+            AssetType::TiledMap => {
+                let data = match report.read(root, path) {
+                    Some(data) => data,
+                    None => continue,
+                };
+                let mut map = match TiledMap::from_slice(&data) {
+                    Ok(map) => map,
+                    Err(e) => {
+                        report.problem(path, e);
+                        continue;
+                    }
+                };
+                map.relative_to(path);
+                for image in map.tileset_images().into_iter().flatten() {
+                    report.image(root, image);
+                }
+                for object in map.objects() {
+                    if !object.name.is_empty() {
+                        names.insert(object.name.clone());
+                    }
+                    for property in object.properties.iter().filter(|p| p.name == "script") {
+                        match property.value.as_str() {
+                            Some(script) => scripts.push((path.to_string(), script.to_string())),
+                            None => report.problem(path, format!("script of {} is not a path", object.name)),
+                        }
+                    }
+                }
+            }
+            #[cfg(feature = "rhai")]
+            AssetType::Script => {
+                if let Some(data) = report.read(root, path) {
+                    let compiled = String::from_utf8(data)
+                        .map_err(|e| e.to_string())
+                        .and_then(|source| crate::world::scripting::compile(&source).map_err(|e| e.to_string()));
+                    if let Err(e) = compiled {
+                        report.problem(path, e);
+                    }
+                }
+            }
+            AssetType::Sound => {
+                if let Some(Err(e)) = report.read(root, path).map(|data| Sound::decode(&data)) {
+                    report.problem(path, e);
+                }
+            }
+            AssetType::Font => {
+                let descriptor = match report.read(root, path).map(String::from_utf8) {
+                    Some(Ok(descriptor)) => descriptor,
+                    Some(Err(e)) => {
+                        report.problem(path, e);
+                        continue;
+                    }
+                    None => continue,
+                };
+                let page = match Font::bmfont_page(&descriptor) {
+                    Some(page) => super::relative_path(path, page),
+                    None => {
+                        report.problem(path, "no page image");
+                        continue;
+                    }
+                };
+                if let Some(data) = report.read(root, &page) {
+                    let font = image::load_from_memory(&data)
+                        .map_err(|e| e.to_string())
+                        .and_then(|page| Font::from_bmfont(&descriptor, &page.to_rgba8()).map_err(|e| e.to_string()));
+                    if let Err(e) = font {
+                        report.problem(path, e);
+                    }
+                }
+            }
+            AssetType::Layout => {
+                let mut layout = match report.read(root, path).map(|data| Layout::from_slice(&data)) {
+                    Some(Ok(layout)) => layout,
+                    Some(Err(e)) => {
+                        report.problem(path, e);
+                        continue;
+                    }
+                    None => continue,
+                };
+                layout.relative_to(path);
+                for image in layout.images() {
+                    report.image(root, image);
+                }
+                let fonts = layout.widgets.iter().filter_map(|w| match &w.kind {
+                    crate::ui::WidgetKind::Label { font: Some(font), .. } => Some(font),
+                    _ => None,
+                });
+                for font in fonts {
+                    if !index.iter().any(|a| &a.path == font && a.r#type == AssetType::Font) {
+                        report.problem(path, format!("font {} is not in the index", font));
+                    }
+                }
+            }
+            AssetType::Unknown => report.problem(path, "unknown asset type"),
+        }
+        if asset.trigger.is_some() && asset.r#type != AssetType::Sound {
+            report.problem(path, "only sounds can have a trigger");
+        }
+    }
+
+    #[cfg(feature = "rhai")]
+    for asset in index.iter().filter(|a| a.r#type == AssetType::Script) {
+        if let Some(target) = &asset.entity_id {
+            if target != "player" && !names.contains(target) {
+                report.problem(&asset.path, format!("no level has an object called {}", target));
+            }
+        }
+    }
+    for (level, script) in scripts {
+        #[cfg(feature = "rhai")]
+        let indexed = index.iter().any(|a| a.path == script && a.r#type == AssetType::Script);
+        #[cfg(not(feature = "rhai"))]
+        let indexed = false;
+        if !indexed {
+            report.problem(&level, format!("script {} is not in the index", script));
+        }
+    }
+    report
+}
@@ -0,0 +1,116 @@
+//! Noticing changed asset files while the game runs, by scanning the asset directory
+
+use super::{Asset, AssetSource, AssetType};
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Time between two scans of the asset directory
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const INDEX: &str = "index.json";
+
+/// Modification time and size of a file
+type Stamp = (SystemTime, u64);
+
+pub struct AssetWatcher {
+    root: PathBuf,
+    source: AssetSource,
+    index: Vec<Asset>,
+    files: BTreeMap<String, Stamp>,
+    last_poll: Instant,
+}
+
+impl AssetWatcher {
+    /// Watch the files of `source`, where everything in `index` is already imported
+    ///
+    /// Only directories can change, there is nothing to watch for other sources.
+    pub fn new(source: &AssetSource, index: Vec<Asset>) -> Option<Self> {
+        source.root().map(|root| Self::with_root(root, index))
+    }
+
+    pub fn with_root(root: impl Into<PathBuf>, index: Vec<Asset>) -> Self {
+        let root = root.into();
+        let files = scan(&root);
+        let source = AssetSource::directory(&root);
+        Self { root, source, index, files, last_poll: Instant::now() }
+    }
+
+    /// Where to read the changed assets from
+    pub fn source(&self) -> &AssetSource {
+        &self.source
+    }
+
+    /// Same as `changed()`, but only scans once every `POLL_INTERVAL`
+    pub fn poll(&mut self) -> Result<Vec<Asset>, Box<dyn Error>> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return Ok(Vec::new());
+        }
+        self.last_poll = Instant::now();
+        self.changed()
+    }
+
+    /// Assets to reload because their files changed since the last scan
+    ///
+    /// A changed index reloads everything in it. Files not in the index may be tilesets, so
+    /// they reload every Tiled map.
+    pub fn changed(&mut self) -> Result<Vec<Asset>, Box<dyn Error>> {
+        let files = scan(&self.root);
+        let changed: Vec<String> = files
+            .iter()
+            .filter(|(path, stamp)| self.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.files = files;
+
+        if changed.iter().any(|path| path == INDEX) {
+            self.index = serde_json::from_slice(&fs::read(self.root.join(INDEX))?)?;
+            return Ok(self.index.clone());
+        }
+
+        let indexed = |path: &String| self.index.iter().any(|asset| &asset.path == path);
+        let maps = changed.iter().any(|path| !indexed(path));
+        Ok(self.index
+            .iter()
+            .filter(|asset| changed.contains(&asset.path) || (maps && asset.r#type == AssetType::TiledMap))
+            .cloned()
+            .collect())
+    }
+}
+
+/// Every file below `root`, by its path relative to it
+fn scan(root: &Path) -> BTreeMap<String, Stamp> {
+    let mut files = BTreeMap::new();
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        let entries = match fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let path = entry.path();
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(_) => continue,
+            };
+            if metadata.is_dir() {
+                directories.push(path);
+                continue;
+            }
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            if let Ok(relative) = path.strip_prefix(root) {
+                let relative = relative.components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.insert(relative, (modified, metadata.len()));
+            }
+        }
+    }
+    files
+}
@@ -0,0 +1,165 @@
+//! Mixing every playing sound into a single stream of samples
+//!
+//! Output backends call `Mixer::mix()` whenever the device wants more samples. Nothing here
+//! depends on a device, so the mixer also works headless.
+
+use super::Sound;
+use crate::world::EntityId;
+
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Sample rate of a new mixer, matching most output devices
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// The mixer always produces stereo, left and right samples interleaved
+pub const CHANNELS: usize = 2;
+
+pub type VoiceId = u64;
+
+/// A sound being played
+#[derive(Clone, Debug)]
+pub struct Voice {
+    pub sound: Arc<Sound>,
+    /// Multiplies every sample, 1 plays the sound as it is
+    pub volume: f32,
+    /// From -1 (left only) through 0 (both) to 1 (right only)
+    pub pan: f32,
+    /// Start over at the end instead of stopping
+    pub looping: bool,
+    /// Entity the sound comes from, placed by `spatial::spatialize()`
+    pub emitter: Option<EntityId>,
+    /// Volume lost to the distance from the listener, 1 for sounds not coming from an entity
+    pub attenuation: f32,
+    /// Frame of `sound` to play next, fractional when sample rates differ
+    position: f64,
+}
+
+impl Voice {
+    pub fn new(sound: Arc<Sound>) -> Self {
+        Self { sound, volume: 1., pan: 0., looping: false, emitter: None, attenuation: 1., position: 0. }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.position >= self.sound.frames() as f64
+    }
+
+    /// Gain of the left and right channels
+    fn gains(&self) -> (f32, f32) {
+        let (pan, volume) = (self.pan.clamp(-1., 1.), self.volume * self.attenuation);
+        (volume * (1. - pan).min(1.), volume * (1. + pan).min(1.))
+    }
+
+    /// Next frame, resampled from the sound's rate to the mixer's
+    fn next_frame(&mut self, step: f64) -> (f32, f32) {
+        let frames = self.sound.frames();
+        if frames == 0 {
+            self.position = 0.;
+            self.looping = false;
+            return (0., 0.);
+        }
+
+        let frame = self.position as usize;
+        let next = if frame + 1 < frames { frame + 1 } else if self.looping { 0 } else { frame };
+        let t = (self.position - frame as f64) as f32;
+        let lerp = |channel| {
+            let (a, b) = (self.sound.sample(frame, channel), self.sound.sample(next, channel));
+            a + (b - a) * t
+        };
+        let sample = (lerp(0), lerp(1));
+
+        self.position += step;
+        if self.looping && self.position >= frames as f64 {
+            self.position %= frames as f64;
+        }
+        sample
+    }
+}
+
+pub struct Mixer {
+    /// Frames per second produced by `mix()`
+    pub sample_rate: u32,
+    /// Master volume, applied after mixing every voice
+    pub volume: f32,
+    voices: BTreeMap<VoiceId, Voice>,
+    next_id: VoiceId,
+}
+
+impl Default for Mixer {
+    fn default() -> Self {
+        Self::new(DEFAULT_SAMPLE_RATE)
+    }
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self { sample_rate, volume: 1., voices: BTreeMap::new(), next_id: 0 }
+    }
+
+    /// Start playing `voice`, returning an id to change or stop it with
+    pub fn play(&mut self, voice: Voice) -> VoiceId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.voices.insert(id, voice);
+        id
+    }
+
+    pub fn voice_mut(&mut self, id: VoiceId) -> Option<&mut Voice> {
+        self.voices.get_mut(&id)
+    }
+
+    pub fn voices_mut(&mut self) -> impl Iterator<Item = &mut Voice> {
+        self.voices.values_mut()
+    }
+
+    pub fn stop(&mut self, id: VoiceId) {
+        self.voices.remove(&id);
+    }
+
+    pub fn stop_all(&mut self) {
+        self.voices.clear();
+    }
+
+    pub fn is_playing(&self, id: VoiceId) -> bool {
+        self.voices.contains_key(&id)
+    }
+
+    /// Number of voices playing
+    pub fn playing(&self) -> usize {
+        self.voices.len()
+    }
+
+    /// Fill `out` with the next interleaved stereo samples, dropping finished voices
+    ///
+    /// Samples are clipped to between -1 and 1. A trailing odd sample is left silent.
+    pub fn mix(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = 0.;
+        }
+
+        let rate = self.sample_rate.max(1) as f64;
+        for voice in self.voices.values_mut() {
+            let step = voice.sound.sample_rate as f64 / rate;
+            let (left, right) = voice.gains();
+            for frame in out.chunks_exact_mut(CHANNELS) {
+                if voice.is_finished() {
+                    break;
+                }
+                let (l, r) = voice.next_frame(step);
+                frame[0] += l * left;
+                frame[1] += r * right;
+            }
+        }
+        self.voices.retain(|_, voice| !voice.is_finished());
+
+        for sample in out.iter_mut() {
+            *sample = (*sample * self.volume).clamp(-1., 1.);
+        }
+    }
+
+    /// The next `frames` frames of interleaved stereo samples
+    pub fn render(&mut self, frames: usize) -> Vec<f32> {
+        let mut out = vec![0.; frames * CHANNELS];
+        self.mix(&mut out);
+        out
+    }
+}
@@ -0,0 +1,59 @@
+//! Sound effects, mixed in software
+//!
+//! Sound assets are decoded into `World::sounds` when imported. After each `World::tick()`,
+//! `play_triggered()` starts the sounds of what happened during it on a `Mixer`, and
+//! `spatial::spatialize()` keeps the sounds of moving entities where they are.
+
+pub mod mixer;
+pub mod sound;
+pub mod spatial;
+
+pub use mixer::{Mixer, Voice, VoiceId};
+pub use sound::Sound;
+pub use spatial::{Listener, Spatial};
+
+use crate::world::{CollisionPhase, World};
+
+use serde::{Serialize, Deserialize};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Gameplay events that can play a sound
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Trigger {
+    /// A player left the ground
+    Jump,
+    /// Two entities started touching
+    Collision,
+}
+
+/// Imported sound assets, and the ones played on gameplay events
+#[derive(Clone, Debug, Default)]
+pub struct Sounds {
+    /// Decoded sounds by asset path
+    pub clips: BTreeMap<String, Arc<Sound>>,
+    /// Asset path of the sound for each trigger
+    pub triggers: BTreeMap<Trigger, String>,
+}
+
+impl Sounds {
+    /// The sound played on `trigger`, if there is one
+    pub fn triggered(&self, trigger: Trigger) -> Option<&Arc<Sound>> {
+        self.triggers.get(&trigger).and_then(|path| self.clips.get(path))
+    }
+}
+
+/// Play the sounds of the events of the last `World::tick()`, from the entities involved
+pub fn play_triggered(world: &World, mixer: &mut Mixer, spatial: &Spatial) {
+    let jumps = world.jumps.iter().map(|id| (Trigger::Jump, *id));
+    let collisions = world.events.iter()
+        .filter(|e| e.phase == CollisionPhase::Begin)
+        .map(|e| (Trigger::Collision, e.a));
+    for (trigger, emitter) in jumps.chain(collisions) {
+        if let Some(sound) = world.sounds.triggered(trigger) {
+            let mut voice = Voice::new(sound.clone());
+            voice.emitter = Some(emitter);
+            mixer.play(voice);
+        }
+    }
+    spatial::spatialize(world, mixer, spatial);
+}
@@ -0,0 +1,86 @@
+//! Decoding sound assets into samples the mixer can play
+
+use std::{error::Error, fmt, io::Cursor};
+
+#[derive(Debug)]
+pub struct SoundError {
+    reason: String,
+}
+impl fmt::Display for SoundError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid sound: {}", self.reason)
+    }
+}
+impl Error for SoundError {}
+
+fn invalid(reason: impl fmt::Display) -> Box<dyn Error> {
+    Box::new(SoundError { reason: reason.to_string() })
+}
+
+/// Decoded audio, kept in memory for as long as it may be played
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sound {
+    /// Frames per second
+    pub sample_rate: u32,
+    pub channels: u16,
+    /// Interleaved, between -1 and 1
+    pub samples: Vec<f32>,
+}
+
+impl Sound {
+    /// Decode a WAV or Ogg Vorbis file
+    pub fn decode(data: &[u8]) -> Result<Sound, Box<dyn Error>> {
+        if data.starts_with(b"RIFF") {
+            decode_wav(data)
+        } else if data.starts_with(b"OggS") {
+            decode_ogg(data)
+        } else {
+            Err(invalid("not a WAV or Ogg file"))
+        }
+    }
+
+    /// Number of samples per channel
+    pub fn frames(&self) -> usize {
+        if self.channels == 0 { 0 } else { self.samples.len() / self.channels as usize }
+    }
+
+    /// Length in seconds
+    pub fn duration(&self) -> f64 {
+        if self.sample_rate == 0 { 0. } else { self.frames() as f64 / self.sample_rate as f64 }
+    }
+
+    /// Sample of `channel` in `frame`, mono sounds play the same on every channel
+    pub fn sample(&self, frame: usize, channel: usize) -> f32 {
+        let channels = self.channels.max(1) as usize;
+        self.samples.get(frame * channels + channel.min(channels - 1)).copied().unwrap_or(0.)
+    }
+}
+
+fn decode_wav(data: &[u8]) -> Result<Sound, Box<dyn Error>> {
+    let reader = hound::WavReader::new(Cursor::new(data)).map_err(invalid)?;
+    let spec = reader.spec();
+    if spec.channels == 0 {
+        return Err(invalid("no channels"));
+    }
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Float => reader.into_samples::<f32>().collect::<Result<Vec<_>, _>>(),
+        hound::SampleFormat::Int => {
+            let scale = 1. / (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader.into_samples::<i32>().map(|s| s.map(|s| s as f32 * scale)).collect()
+        }
+    };
+    Ok(Sound { sample_rate: spec.sample_rate, channels: spec.channels, samples: samples.map_err(invalid)? })
+}
+
+fn decode_ogg(data: &[u8]) -> Result<Sound, Box<dyn Error>> {
+    let mut reader = lewton::inside_ogg::OggStreamReader::new(Cursor::new(data)).map_err(invalid)?;
+    let (sample_rate, channels) = (reader.ident_hdr.audio_sample_rate, reader.ident_hdr.audio_channels as u16);
+    if channels == 0 {
+        return Err(invalid("no channels"));
+    }
+    let mut samples = Vec::new();
+    while let Some(packet) = reader.read_dec_packet_itl().map_err(invalid)? {
+        samples.extend(packet.into_iter().map(|s| s as f32 / 32768.));
+    }
+    Ok(Sound { sample_rate, channels, samples })
+}
@@ -0,0 +1,87 @@
+//! Fading and panning sounds by where their entity is relative to the listener
+
+use super::Mixer;
+use crate::world::{Coord, World};
+
+/// Where sounds are heard from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Listener {
+    /// The center of the screen, at `World::scroll`
+    Camera,
+    /// The player closest to each sound
+    Player,
+}
+
+/// How distance to the listener changes the volume and side of a sound
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Spatial {
+    pub listener: Listener,
+    /// Sounds closer than this play at full volume
+    pub min_distance: f64,
+    /// Sounds further than this are not heard at all
+    pub max_distance: f64,
+    /// How fast sounds fade past `min_distance`, 0 keeps them at full volume
+    pub rolloff: f64,
+    /// Horizontal distance at which sounds are only heard on one side
+    pub pan_distance: f64,
+}
+
+impl Default for Spatial {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Spatial {
+    pub fn new() -> Self {
+        Self { listener: Listener::Camera, min_distance: 64., max_distance: 1000., rolloff: 1., pan_distance: 320. }
+    }
+
+    /// Position `source` is heard from, `None` without players to listen
+    pub fn listener_position(&self, world: &World, source: Coord) -> Option<Coord> {
+        match self.listener {
+            Listener::Camera => Some(Coord { x: world.scroll.0, y: world.scroll.1, z: 0. }),
+            Listener::Player => {
+                let c = &world.components;
+                let distance = |pos: &Coord| (pos.x - source.x).powi(2) + (pos.y - source.y).powi(2);
+                world.players()
+                    .filter_map(|id| c.position.get(id).copied())
+                    .min_by(|a, b| distance(a).partial_cmp(&distance(b)).unwrap_or(core::cmp::Ordering::Equal))
+            }
+        }
+    }
+
+    /// Attenuation and pan of a sound at `source` heard from `listener`
+    ///
+    /// Volume falls off with the inverse of the distance past `min_distance`.
+    pub fn place(&self, listener: Coord, source: Coord) -> (f32, f32) {
+        let (dx, dy) = (source.x - listener.x, source.y - listener.y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        let gain = if distance >= self.max_distance {
+            0.
+        } else if distance <= self.min_distance {
+            1.
+        } else {
+            self.min_distance / (self.min_distance + self.rolloff * (distance - self.min_distance))
+        };
+        let pan = if self.pan_distance > 0. { (dx / self.pan_distance).clamp(-1., 1.) } else { 0. };
+        (gain as f32, pan as f32)
+    }
+}
+
+/// Fade and pan every voice coming from an entity, for where it is now
+///
+/// Voices of despawned entities keep how they sounded last.
+pub fn spatialize(world: &World, mixer: &mut Mixer, spatial: &Spatial) {
+    for voice in mixer.voices_mut() {
+        let pos = match voice.emitter.and_then(|id| world.components.position.get(id)) {
+            Some(pos) => *pos,
+            None => continue,
+        };
+        if let Some(listener) = spatial.listener_position(world, pos) {
+            let (attenuation, pan) = spatial.place(listener, pos);
+            voice.attenuation = attenuation;
+            voice.pan = pan;
+        }
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "native")]
+
+//! Checks an asset directory, and packs it into a single file for deployment
+//!
+//! Usage: `assetpack [--pack[=OUTPUT]] [ASSET_DIR]`
+//!
+//! Checks `./assets` by default. With `--pack`, also writes `./assets.pack` or `OUTPUT` if
+//! there were no problems.
+
+use canvasgame_rust::assets::{pack::AssetPack, validate::validate, PACK_PATH};
+use std::{error::Error, fs, path::PathBuf};
+
+struct Options {
+    root: PathBuf,
+    pack: Option<String>,
+}
+
+fn options() -> Options {
+    let mut options = Options { root: PathBuf::from("./assets"), pack: None };
+    for arg in std::env::args().skip(1) {
+        if arg == "--pack" {
+            options.pack = Some(PACK_PATH.to_string());
+        } else if let Some(output) = arg.strip_prefix("--pack=") {
+            options.pack = Some(output.to_string());
+        } else {
+            options.root = PathBuf::from(arg);
+        }
+    }
+    options
+}
+
+fn run() -> Result<bool, Box<dyn Error>> {
+    let options = options();
+
+    let report = validate(&options.root);
+    for (path, size) in report.sizes.iter() {
+        println!("{:>10}  {}", size, path);
+    }
+    println!("{:>10}  total", report.total_size());
+
+    if !report.is_ok() {
+        eprintln!();
+        for problem in report.problems.iter() {
+            eprintln!("error: {}", problem);
+        }
+        return Ok(false);
+    }
+
+    if let Some(output) = options.pack {
+        let data = AssetPack::from_dir(&options.root)?;
+        fs::write(&output, &data)?;
+        println!("Wrote {} ({} bytes)", output, data.len());
+    }
+    Ok(true)
+}
+
+fn main() {
+    match run() {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("Could not pack assets: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -2,6 +2,10 @@ use super::*;
 
 pub struct BareEngine<'a> {
     pub world: crate::world::World,
+    /// Filled in by the caller, who owns the clock
+    pub stats: DebugStats,
+    font: Font,
+    navigation: Option<(usize, NavGrid)>,
     w: usize,
     h: usize,
     set_pixel: &'a mut dyn FnMut(usize, usize, u8, u8, u8) -> (),
@@ -16,6 +20,9 @@ impl<'a> BareEngine<'a> {
     ) -> Self {
         Self {
             world,
+            stats: DebugStats::new(),
+            font: Font::builtin(),
+            navigation: None,
             w,
             h,
             set_pixel
@@ -27,7 +34,13 @@ impl<'a> BareEngine<'a> {
     }
     pub fn render(&mut self) {
         self.clear();
-        self.render_world(&self.world.clone());
+        let world = self.world.clone();
+        self.render_world(&world);
+        if SHOW_DEBUG.load(Ordering::Relaxed) {
+            let font = self.font.clone();
+            let stats = self.stats;
+            self.render_debug(&world, &stats, &font);
+        }
     }
 }
 
@@ -54,4 +67,7 @@ impl Engine for BareEngine<'_> {
     fn set_at_with_opacity(&mut self, _idx: usize, _pixel: Pixel, _opacity: f64) {
         // No-op here, we don't use opacity in this implementation
     }
+    fn navigation_grid(&mut self) -> Option<&mut Option<(usize, NavGrid)>> {
+        Some(&mut self.navigation)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,59 @@
+//! Measurements shown by the debug overlay, see `Engine::render_debug()`
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// Draw the debug overlay over the world
+pub static SHOW_DEBUG: AtomicBool = AtomicBool::new(false);
+
+pub fn toggle_debug() {
+    SHOW_DEBUG.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// Time between two updates of `DebugStats::fps`, in milliseconds
+const FPS_INTERVAL: f64 = 1000.;
+
+/// Frame rate and tick duration, fed by the engine with its own clock
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DebugStats {
+    /// Frames rendered per second, over the last second
+    pub fps: f64,
+    /// Duration of the last `World::tick()`, in milliseconds
+    pub tick_time: f64,
+    frames: u32,
+    since: Option<f64>,
+}
+
+impl DebugStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a frame rendered at `now`, in milliseconds
+    pub fn frame(&mut self, now: f64) {
+        let since = match self.since {
+            Some(since) => since,
+            None => {
+                // The first frame only starts the clock
+                self.since = Some(now);
+                return;
+            }
+        };
+        self.frames += 1;
+        if now - since >= FPS_INTERVAL {
+            self.fps = self.frames as f64 * 1000. / (now - since);
+            self.frames = 0;
+            self.since = Some(now);
+        }
+    }
+
+    /// Count frames from the next one again, after the engine did not run for a while
+    pub fn restart(&mut self) {
+        self.frames = 0;
+        self.since = None;
+    }
+
+    /// Note a tick that ran from `start` to `end`, in milliseconds
+    pub fn tick(&mut self, start: f64, end: f64) {
+        self.tick_time = end - start;
+    }
+}
@@ -0,0 +1,244 @@
+//! Bitmap fonts for `Engine::draw_text()`
+//!
+//! Glyphs are cut from an atlas where every pixel is either ink or not, so text looks the
+//! same on every backend. `Font::builtin()` needs no assets, other fonts come from a fixed
+//! grid image or an [AngelCode BMFont](https://www.angelcode.com/products/bmfont/) text file.
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(not(feature = "alloc"))]
+use std::collections::BTreeMap;
+
+#[cfg(not(feature = "bare"))]
+use image::RgbaImage;
+#[cfg(not(feature = "bare"))]
+use std::{convert::TryFrom, error::Error, fmt};
+
+/// Where a character is in the atlas, and how it sits on the line
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Glyph {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Offset from the pen position to the top left corner of the glyph
+    pub x_offset: i32,
+    pub y_offset: i32,
+    /// How far the pen moves after the glyph
+    pub advance: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Font {
+    /// Distance between two lines
+    pub line_height: usize,
+    glyphs: BTreeMap<char, Glyph>,
+    atlas: Vec<bool>,
+    atlas_width: usize,
+}
+
+/// The font of `Font::builtin()`: 5x7 glyphs for ASCII 32 to 126, one byte per column with
+/// the top row in the lowest bit
+const BUILTIN: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], [0x00, 0x00, 0x5F, 0x00, 0x00], [0x00, 0x07, 0x00, 0x07, 0x00],
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], [0x24, 0x2A, 0x7F, 0x2A, 0x12], [0x23, 0x13, 0x08, 0x64, 0x62],
+    [0x36, 0x49, 0x56, 0x20, 0x50], [0x00, 0x08, 0x07, 0x03, 0x00], [0x00, 0x1C, 0x22, 0x41, 0x00],
+    [0x00, 0x41, 0x22, 0x1C, 0x00], [0x2A, 0x1C, 0x7F, 0x1C, 0x2A], [0x08, 0x08, 0x3E, 0x08, 0x08],
+    [0x00, 0x50, 0x30, 0x00, 0x00], [0x08, 0x08, 0x08, 0x08, 0x08], [0x00, 0x60, 0x60, 0x00, 0x00],
+    [0x20, 0x10, 0x08, 0x04, 0x02], [0x3E, 0x51, 0x49, 0x45, 0x3E], [0x00, 0x42, 0x7F, 0x40, 0x00],
+    [0x42, 0x61, 0x51, 0x49, 0x46], [0x21, 0x41, 0x45, 0x4B, 0x31], [0x18, 0x14, 0x12, 0x7F, 0x10],
+    [0x27, 0x45, 0x45, 0x45, 0x39], [0x3C, 0x4A, 0x49, 0x49, 0x30], [0x01, 0x71, 0x09, 0x05, 0x03],
+    [0x36, 0x49, 0x49, 0x49, 0x36], [0x06, 0x49, 0x49, 0x29, 0x1E], [0x00, 0x36, 0x36, 0x00, 0x00],
+    [0x00, 0x56, 0x36, 0x00, 0x00], [0x08, 0x14, 0x22, 0x41, 0x00], [0x14, 0x14, 0x14, 0x14, 0x14],
+    [0x00, 0x41, 0x22, 0x14, 0x08], [0x02, 0x01, 0x51, 0x09, 0x06], [0x32, 0x49, 0x79, 0x41, 0x3E],
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], [0x7F, 0x49, 0x49, 0x49, 0x36], [0x3E, 0x41, 0x41, 0x41, 0x22],
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], [0x7F, 0x49, 0x49, 0x49, 0x41], [0x7F, 0x09, 0x09, 0x09, 0x01],
+    [0x3E, 0x41, 0x49, 0x49, 0x7A], [0x7F, 0x08, 0x08, 0x08, 0x7F], [0x00, 0x41, 0x7F, 0x41, 0x00],
+    [0x20, 0x40, 0x41, 0x3F, 0x01], [0x7F, 0x08, 0x14, 0x22, 0x41], [0x7F, 0x40, 0x40, 0x40, 0x40],
+    [0x7F, 0x02, 0x0C, 0x02, 0x7F], [0x7F, 0x04, 0x08, 0x10, 0x7F], [0x3E, 0x41, 0x41, 0x41, 0x3E],
+    [0x7F, 0x09, 0x09, 0x09, 0x06], [0x3E, 0x41, 0x51, 0x21, 0x5E], [0x7F, 0x09, 0x19, 0x29, 0x46],
+    [0x46, 0x49, 0x49, 0x49, 0x31], [0x01, 0x01, 0x7F, 0x01, 0x01], [0x3F, 0x40, 0x40, 0x40, 0x3F],
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], [0x3F, 0x40, 0x38, 0x40, 0x3F], [0x63, 0x14, 0x08, 0x14, 0x63],
+    [0x07, 0x08, 0x70, 0x08, 0x07], [0x61, 0x51, 0x49, 0x45, 0x43], [0x00, 0x7F, 0x41, 0x41, 0x00],
+    [0x02, 0x04, 0x08, 0x10, 0x20], [0x00, 0x41, 0x41, 0x7F, 0x00], [0x04, 0x02, 0x01, 0x02, 0x04],
+    [0x40, 0x40, 0x40, 0x40, 0x40], [0x00, 0x01, 0x02, 0x04, 0x00], [0x20, 0x54, 0x54, 0x54, 0x78],
+    [0x7F, 0x48, 0x44, 0x44, 0x38], [0x38, 0x44, 0x44, 0x44, 0x20], [0x38, 0x44, 0x44, 0x48, 0x7F],
+    [0x38, 0x54, 0x54, 0x54, 0x18], [0x08, 0x7E, 0x09, 0x01, 0x02], [0x0C, 0x52, 0x52, 0x52, 0x3E],
+    [0x7F, 0x08, 0x04, 0x04, 0x78], [0x00, 0x44, 0x7D, 0x40, 0x00], [0x20, 0x40, 0x44, 0x3D, 0x00],
+    [0x7F, 0x10, 0x28, 0x44, 0x00], [0x00, 0x41, 0x7F, 0x40, 0x00], [0x7C, 0x04, 0x18, 0x04, 0x78],
+    [0x7C, 0x08, 0x04, 0x04, 0x78], [0x38, 0x44, 0x44, 0x44, 0x38], [0x7C, 0x14, 0x14, 0x14, 0x08],
+    [0x08, 0x14, 0x14, 0x18, 0x7C], [0x7C, 0x08, 0x04, 0x04, 0x08], [0x48, 0x54, 0x54, 0x54, 0x20],
+    [0x04, 0x3F, 0x44, 0x40, 0x20], [0x3C, 0x40, 0x40, 0x20, 0x7C], [0x1C, 0x20, 0x40, 0x20, 0x1C],
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], [0x44, 0x28, 0x10, 0x28, 0x44], [0x0C, 0x50, 0x50, 0x50, 0x3C],
+    [0x44, 0x64, 0x54, 0x4C, 0x44], [0x00, 0x08, 0x36, 0x41, 0x00], [0x00, 0x00, 0x7F, 0x00, 0x00],
+    [0x00, 0x41, 0x36, 0x08, 0x00], [0x02, 0x01, 0x02, 0x04, 0x02],
+];
+
+impl Default for Font {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Font {
+    /// Small 5x7 font covering printable ASCII, always available
+    pub fn builtin() -> Self {
+        let atlas_width = BUILTIN.len() * 5;
+        let mut atlas = Vec::new();
+        atlas.resize(atlas_width * 7, false);
+        let mut glyphs = BTreeMap::new();
+        for (i, columns) in BUILTIN.iter().enumerate() {
+            for (column, bits) in columns.iter().enumerate() {
+                for row in 0..7 {
+                    atlas[row * atlas_width + i * 5 + column] = bits & (1 << row) != 0;
+                }
+            }
+            let glyph = Glyph { x: i * 5, y: 0, width: 5, height: 7, x_offset: 0, y_offset: 0, advance: 6 };
+            glyphs.insert((b' ' + i as u8) as char, glyph);
+        }
+        Self { line_height: 8, glyphs, atlas, atlas_width }
+    }
+
+    /// Glyphs of `width` by `height` pixels in rows on `atlas`, from `first` onwards
+    #[cfg(not(feature = "bare"))]
+    pub fn from_grid(atlas: &RgbaImage, width: usize, height: usize, first: char) -> Self {
+        let mut font = Self::with_atlas(atlas, height);
+        if width == 0 || height == 0 {
+            return font;
+        }
+        let columns = font.atlas_width / width;
+        let rows = atlas.height() as usize / height;
+        for i in 0..columns * rows {
+            let c = match core::char::from_u32(first as u32 + i as u32) {
+                Some(c) => c,
+                None => continue,
+            };
+            let (x, y) = (i % columns * width, i / columns * height);
+            let glyph = Glyph { x, y, width, height, x_offset: 0, y_offset: 0, advance: width as i32 };
+            font.glyphs.insert(c, glyph);
+        }
+        font
+    }
+
+    /// Font described by a BMFont text file, with its single page already loaded
+    #[cfg(not(feature = "bare"))]
+    pub fn from_bmfont(descriptor: &str, page: &RgbaImage) -> Result<Self, Box<dyn Error>> {
+        let mut font = Self::with_atlas(page, 0);
+        for line in descriptor.lines() {
+            let tag = line.split_whitespace().next().unwrap_or_default();
+            let value = |key: &str| -> Result<i64, Box<dyn Error>> {
+                line.split_whitespace()
+                    .filter_map(|word| word.split_once('='))
+                    .find(|(k, _)| *k == key)
+                    .ok_or_else(|| invalid(format!("{} without {}", tag, key)))
+                    .and_then(|(_, v)| v.trim_matches('"').parse().map_err(|_| invalid(format!("bad {} in {}", key, line))))
+            };
+            // Sizes and positions on the page cannot be negative
+            let size = |key: &str| -> Result<usize, Box<dyn Error>> {
+                usize::try_from(value(key)?).map_err(|_| invalid(format!("negative {} in {}", key, line)))
+            };
+            let offset = |key: &str| -> Result<i32, Box<dyn Error>> {
+                i32::try_from(value(key)?).map_err(|_| invalid(format!("bad {} in {}", key, line)))
+            };
+            match tag {
+                "common" => {
+                    font.line_height = size("lineHeight")?;
+                    if value("pages").unwrap_or(1) > 1 {
+                        return Err(invalid("only fonts with a single page are supported"));
+                    }
+                }
+                "char" => {
+                    let c = u32::try_from(value("id")?).ok()
+                        .and_then(core::char::from_u32)
+                        .ok_or_else(|| invalid(format!("bad id in {}", line)))?;
+                    let glyph = Glyph {
+                        x: size("x")?,
+                        y: size("y")?,
+                        width: size("width")?,
+                        height: size("height")?,
+                        x_offset: offset("xoffset")?,
+                        y_offset: offset("yoffset")?,
+                        advance: offset("xadvance")?,
+                    };
+                    let inside = matches!(glyph.x.checked_add(glyph.width), Some(right) if right <= font.atlas_width)
+                        && matches!(glyph.y.checked_add(glyph.height), Some(bottom) if bottom <= page.height() as usize);
+                    if !inside {
+                        return Err(invalid(format!("{:?} is outside the page", c)));
+                    }
+                    font.glyphs.insert(c, glyph);
+                }
+                _ => {}
+            }
+        }
+        if font.glyphs.is_empty() {
+            return Err(invalid("no characters"));
+        }
+        Ok(font)
+    }
+
+    /// File name of the page image of a BMFont text file
+    pub fn bmfont_page(descriptor: &str) -> Option<&str> {
+        descriptor.lines()
+            .filter(|line| line.starts_with("page "))
+            .flat_map(|line| line.split_whitespace())
+            .find_map(|word| word.strip_prefix("file="))
+            .map(|file| file.trim_matches('"'))
+    }
+
+    /// Font without glyphs on `atlas`, where bright opaque pixels are ink
+    #[cfg(not(feature = "bare"))]
+    fn with_atlas(atlas: &RgbaImage, line_height: usize) -> Self {
+        let ink = |p: &image::Rgba<u8>| {
+            let brightness = p[0].max(p[1]).max(p[2]) as u32;
+            brightness * p[3] as u32 >= 128 * 255
+        };
+        Self {
+            line_height,
+            glyphs: BTreeMap::new(),
+            atlas: atlas.pixels().map(ink).collect(),
+            atlas_width: atlas.width() as usize,
+        }
+    }
+
+    /// The glyph of `c`, or of `?` if the font does not have it
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs.get(&c).or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Whether the pixel at (x, y) of `glyph` is ink
+    pub fn is_ink(&self, glyph: &Glyph, x: usize, y: usize) -> bool {
+        x < glyph.width && y < glyph.height
+            && self.atlas.get((glyph.y + y) * self.atlas_width + glyph.x + x).copied().unwrap_or(false)
+    }
+
+    /// Width and height of `text` in font pixels, `\n` starts a new line
+    pub fn measure(&self, text: &str) -> (usize, usize) {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.split('\n') {
+            let advance: i32 = line.chars().filter_map(|c| self.glyph(c)).map(|g| g.advance).sum();
+            width = width.max(advance.max(0) as usize);
+            lines += 1;
+        }
+        (width, lines * self.line_height)
+    }
+}
+
+#[cfg(not(feature = "bare"))]
+#[derive(Debug)]
+pub struct FontError {
+    reason: String,
+}
+#[cfg(not(feature = "bare"))]
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid font: {}", self.reason)
+    }
+}
+#[cfg(not(feature = "bare"))]
+impl Error for FontError {}
+
+#[cfg(not(feature = "bare"))]
+fn invalid(reason: impl Into<String>) -> Box<dyn Error> {
+    Box::new(FontError { reason: reason.into() })
+}
@@ -1,7 +1,18 @@
-use crate::world::{Entity, World};
+use crate::world::{navigation, CollisionPhase, Coord, EntityId, NavGrid, Region, Tilemap, World};
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "alloc")]
+use alloc::{format, vec::Vec};
 
 #[cfg(not(feature = "bare"))]
-use image::RgbaImage;
+use {image::RgbaImage, std::{collections::BTreeMap, sync::Arc}};
+
+pub mod debug;
+pub mod font;
+
+pub use debug::{toggle_debug, DebugStats, SHOW_DEBUG};
+pub use font::Font;
 
 #[cfg(target_arch = "wasm32")]
 pub mod web;
@@ -14,6 +25,8 @@ pub mod bare;
 
 pub type Pixel = (u8, u8, u8);
 
+const LOADING_BAR_HEIGHT: f64 = 12.;
+
 pub trait Engine {
     fn width(&self) -> usize;
     fn height(&self) -> usize;
@@ -60,6 +73,163 @@ pub trait Engine {
         }
     }
 
+    /// Same as `fill_rect()`, but draws the part that is on screen instead of skipping it
+    fn fill_rect_clipped(&mut self, x: f64, y: f64, w: f64, h: f64, pixel: Pixel) {
+        let (x0, y0) = (x.max(0.), y.max(0.));
+        let x1 = (x + w).min(self.width() as f64);
+        let y1 = (y + h).min(self.height() as f64);
+        if x1 <= x0 || y1 <= y0 {
+            return;
+        }
+        self.fill_rect(x0 as usize, y0 as usize, (x1 - x0) as usize, (y1 - y0) as usize, pixel);
+    }
+
+    /// Draw `text` with its top left corner at (x, y), each font pixel `scale` pixels wide
+    ///
+    /// `\n` starts a new line. Drawn as rectangles, so it looks the same on every backend.
+    fn draw_text(&mut self, font: &Font, text: &str, x: f64, y: f64, scale: usize, color: Pixel) {
+        let scale = scale.max(1) as f64;
+        let mut line_y = y;
+        for line in text.split('\n') {
+            let mut pen = x;
+            for glyph in line.chars().filter_map(|c| font.glyph(c)) {
+                let left = pen + glyph.x_offset as f64 * scale;
+                let top = line_y + glyph.y_offset as f64 * scale;
+                for row in 0..glyph.height {
+                    // Runs of ink in a row are drawn at once
+                    let mut column = 0;
+                    while column < glyph.width {
+                        if !font.is_ink(glyph, column, row) {
+                            column += 1;
+                            continue;
+                        }
+                        let start = column;
+                        while column < glyph.width && font.is_ink(glyph, column, row) {
+                            column += 1;
+                        }
+                        let (run_x, run_y) = (left + start as f64 * scale, top + row as f64 * scale);
+                        self.fill_rect_clipped(run_x, run_y, (column - start) as f64 * scale, scale, color);
+                    }
+                }
+                pen += glyph.advance as f64 * scale;
+            }
+            line_y += font.line_height as f64 * scale;
+        }
+    }
+
+    /// Widgets of `world.ui` in screen space, over the world
+    #[cfg(not(feature = "bare"))]
+    fn render_ui(&mut self, world: &World) {
+        self.render_widgets(&world.ui, &world.fonts);
+    }
+
+    /// Widgets of `ui` in screen space, with labels in `fonts`
+    #[cfg(not(feature = "bare"))]
+    fn render_widgets(&mut self, ui: &crate::ui::Ui, fonts: &BTreeMap<String, Arc<Font>>) {
+        use crate::ui::WidgetKind;
+
+        let screen = (self.width() as f64, self.height() as f64);
+        for widget in ui.widgets.iter().filter(|w| w.visible) {
+            let (x, y, w, h) = ui.rect(widget, screen, fonts);
+            match &widget.kind {
+                WidgetKind::Label { text, font, scale, color } => {
+                    self.draw_text(ui.font_of(font.as_deref(), fonts), text, x, y, *scale, *color);
+                }
+                WidgetKind::Bar { value, max, color, background } => {
+                    let filled = if *max > 0. { (value / max).clamp(0., 1.) } else { 0. };
+                    self.fill_rect_clipped(x, y, w, h, *background);
+                    self.fill_rect_clipped(x, y, (w * filled).round(), h, *color);
+                }
+                WidgetKind::Icon { image } => {
+                    if let Some(image) = ui.images.get(image) {
+                        let part = (0, 0, image.width().min(w as u32), image.height().min(h as u32));
+                        self.fill_bitmap_part(image, part, x as i64, y as i64);
+                    }
+                }
+                WidgetKind::Panel { color, border } => {
+                    self.fill_rect_clipped(x, y, w, h, *color);
+                    if let Some(border) = border {
+                        self.stroke_rect(x, y, w, h, *border);
+                    }
+                }
+                WidgetKind::Button { text, color, text_color } => {
+                    self.fill_rect_clipped(x, y, w, h, *color);
+                    let (tw, th) = ui.font.measure(text);
+                    let (tx, ty) = ((x + (w - tw as f64) / 2.).round(), (y + (h - th as f64) / 2.).round());
+                    self.draw_text(&ui.font, text, tx, ty, 1, *text_color);
+                }
+            }
+        }
+    }
+
+    /// Outline of `region`, 1 pixel wide
+    fn draw_region(&mut self, region: Region, offset: (f64, f64), pixel: Pixel) {
+        let center = self.center();
+        let x = center.0 + region.start.x - offset.0;
+        let y = center.1 + region.start.y - offset.1;
+        self.stroke_rect(x, y, region.end.x - region.start.x, region.end.y - region.start.y, pixel);
+    }
+
+    /// Outline of a rectangle on screen, 1 pixel wide and clipped like `fill_rect_clipped()`
+    fn stroke_rect(&mut self, x: f64, y: f64, w: f64, h: f64, pixel: Pixel) {
+        self.fill_rect_clipped(x, y, w, 1., pixel);
+        self.fill_rect_clipped(x, y + h - 1., w, 1., pixel);
+        self.fill_rect_clipped(x, y, 1., h, pixel);
+        self.fill_rect_clipped(x + w - 1., y, 1., h, pixel);
+    }
+
+    /// Hitboxes, contacts and numbers about `world` and the engine, over everything else
+    fn render_debug(&mut self, world: &World, stats: &DebugStats, font: &Font) {
+        let c = &world.components;
+        for (id, collider) in c.collider.iter() {
+            if let Some(pos) = c.position.get(id) {
+                let color = if collider.solid { (0, 255, 0) } else { (255, 255, 0) };
+                self.draw_region(collider.absolute(*pos), world.scroll, color);
+            }
+        }
+
+        // Contacts are marked between the two entities, with a line along the normal
+        let center = self.center();
+        for event in world.events.iter().filter(|e| e.phase != CollisionPhase::End) {
+            let (a, b) = match (c.position.get(event.a), c.position.get(event.b)) {
+                (Some(a), Some(b)) => (*a, *b),
+                _ => continue,
+            };
+            let x = center.0 + (a.x + b.x) / 2. - world.scroll.0;
+            let y = center.1 + (a.y + b.y) / 2. - world.scroll.1;
+            self.fill_rect_clipped(x - 2., y - 2., 4., 4., (255, 0, 0));
+            let (nx, ny) = (event.normal.x * 8., event.normal.y * 8.);
+            self.fill_rect_clipped(x.min(x + nx), y.min(y + ny), nx.abs().max(1.), ny.abs().max(1.), (255, 0, 0));
+        }
+
+        let mut text = format!(
+            "FPS: {:.1}  tick: {:.2} ms\nentities: {}\nscroll: {:.1}, {:.1}",
+            stats.fps, stats.tick_time, world.entities.len(), world.scroll.0, world.scroll.1,
+        );
+        for id in world.players() {
+            let pos = c.position.get(id).copied().unwrap_or_else(Coord::origin);
+            let vel = c.velocity.get(id).map_or_else(Coord::origin, |v| v.to);
+            text += &format!(
+                "\nplayer {}: pos {:.1}, {:.1}, {:.1}  vel {:.2}, {:.2}, {:.2}  {}",
+                id, pos.x, pos.y, pos.z, vel.x, vel.y, vel.z, if world.on_ground(id) { "on ground" } else { "in air" },
+            );
+        }
+        let (w, h) = font.measure(&text);
+        self.fill_rect_clipped(4., 4., w as f64 + 8., h as f64 + 8., (0, 0, 0));
+        self.draw_text(font, &text, 8., 8., 1, (255, 255, 255));
+    }
+
+    /// Loading screen with a progress bar, `fraction` is between 0 and 1
+    fn render_loading(&mut self, fraction: f64) {
+        let (w, h) = (self.width() as f64, self.height() as f64);
+        let bar_w = (w * 0.6).min(400.);
+        let (x, y) = ((w - bar_w) / 2., (h - LOADING_BAR_HEIGHT) / 2.);
+        self.clear();
+        self.fill_rect_clipped(x - 2., y - 2., bar_w + 4., LOADING_BAR_HEIGHT + 4., (96, 96, 96));
+        self.fill_rect_clipped(x, y, bar_w, LOADING_BAR_HEIGHT, (0, 0, 0));
+        self.fill_rect_clipped(x, y, bar_w * fraction.clamp(0., 1.), LOADING_BAR_HEIGHT, (255, 255, 255));
+    }
+
     #[cfg(not(feature = "bare"))]
     fn fill_bitmap(&mut self, bitmap: &RgbaImage, x: usize, y: usize) {
         for i in 0..bitmap.width() {
@@ -75,14 +245,85 @@ pub trait Engine {
         }
     }
 
-    fn render_entity(&mut self, entity: &Entity, offset: (f64, f64)) {
-        let size_mult = 1. / (entity.hitbox.start.z / (entity.pos.z + entity.hitbox.start.z));
+    /// Draw the `(x, y, w, h)` part of `bitmap` with its top left corner at `(x, y)` on screen,
+    /// leaving out everything off screen
+    #[cfg(not(feature = "bare"))]
+    fn fill_bitmap_part(&mut self, bitmap: &RgbaImage, part: (u32, u32, u32, u32), x: i64, y: i64) {
+        let (gw, gh) = (self.width() as i64, self.height() as i64);
+        for j in 0..part.3 {
+            let sy = y + j as i64;
+            if sy < 0 || sy >= gh {
+                continue;
+            }
+            for i in 0..part.2 {
+                let sx = x + i as i64;
+                if sx < 0 || sx >= gw {
+                    continue;
+                }
+                let p = bitmap.get_pixel(part.0 + i, part.1 + j);
+                if p[3] == 0 {
+                    continue;
+                }
+                let opacity = p[3] as f64 / 255.;
+                if opacity == 1. {
+                    self.set(sx as usize, sy as usize, (p[0], p[1], p[2]));
+                } else {
+                    self.set_with_opacity(sx as usize, sy as usize, (p[0], p[1], p[2]), opacity);
+                }
+            }
+        }
+    }
+
+    /// Draw only the tiles of `tilemap` that are on screen
+    fn render_tilemap(&mut self, tilemap: &Tilemap, offset: (f64, f64)) {
+        let center = self.center();
+        let (gw, gh) = (self.width() as f64, self.height() as f64);
+        let visible = Region {
+            start: Coord { x: offset.0 - center.0, y: offset.1 - center.1, z: 0. },
+            end: Coord { x: offset.0 - center.0 + gw, y: offset.1 - center.1 + gh, z: 0. },
+        };
+
+        let (columns, rows) = tilemap.cells_in(visible);
+        for row in rows {
+            for column in columns.clone() {
+                if tilemap.get(column, row).is_none() {
+                    continue;
+                }
+                let cell = tilemap.cell_region(column, row);
+                let x = center.0 + cell.start.x - offset.0;
+                let y = center.1 + cell.start.y - offset.1;
+
+                #[cfg(not(feature = "bare"))]
+                {
+                    let part = tilemap.get(column, row).and_then(|t| tilemap.tileset_rect(t));
+                    if let (Some(part), Some(tileset)) = (part, &tilemap.tileset) {
+                        self.fill_bitmap_part(tileset, part, x as i64, y as i64);
+                        continue;
+                    }
+                }
+
+                // Missing tileset
+                let (w, h) = (tilemap.tile_size.0 as f64, tilemap.tile_size.1 as f64);
+                let color = if tilemap.is_solid(column, row) { (96, 96, 96) } else { (48, 48, 48) };
+                self.fill_rect_clipped(x, y, w, h, color);
+            }
+        }
+    }
+
+    fn render_entity(&mut self, world: &World, id: EntityId, offset: (f64, f64)) {
+        let pos = match world.components.position.get(id) {
+            Some(pos) => *pos,
+            None => return,
+        };
+        let hitbox = world.components.collider.get(id).map_or(Region::default_hitbox(), |c| c.hitbox);
+
+        let size_mult = 1. / (hitbox.start.z / (pos.z + hitbox.start.z));
         let center = self.center();
 
-        let x = center.0 + entity.pos.x - offset.0 + (entity.hitbox.start.x * size_mult);
-        let y = center.1 + entity.pos.y - offset.1 + (entity.hitbox.start.y * size_mult);
-        let w = center.0 + entity.pos.x - offset.0 + (entity.hitbox.end.x * size_mult) - x;
-        let h = center.1 + entity.pos.y - offset.1 + (entity.hitbox.end.y * size_mult) - y;
+        let x = center.0 + pos.x - offset.0 + (hitbox.start.x * size_mult);
+        let y = center.1 + pos.y - offset.1 + (hitbox.start.y * size_mult);
+        let w = center.0 + pos.x - offset.0 + (hitbox.end.x * size_mult) - x;
+        let h = center.1 + pos.y - offset.1 + (hitbox.end.y * size_mult) - y;
 
         if x < 0. || y < 0. {
             return;
@@ -90,7 +331,7 @@ pub trait Engine {
 
         #[cfg(not(feature = "bare"))]
         {
-            if let Some(bitmap) = &entity.texture {
+            if let Some(bitmap) = world.components.sprite.get(id).and_then(|s| s.texture.as_ref()) {
                 self.fill_bitmap(bitmap, x as usize, y as usize);
                 return;
             }
@@ -100,13 +341,114 @@ pub trait Engine {
     }
 
     fn render_world(&mut self, world: &World) {
-        for entity in world.entities.iter() {
-            self.render_entity(&entity, world.scroll);
+        for tilemap in world.tilemaps.iter() {
+            self.render_tilemap(tilemap, world.scroll);
+        }
+
+        // Players go last, on top of everything else
+        let players = &world.components.player;
+        for id in world.components.position.ids().filter(|id| !players.contains(*id)) {
+            self.render_entity(world, id, world.scroll);
+        }
+        for id in players.ids() {
+            self.render_entity(world, id, world.scroll);
+        }
+
+        if SHOW_NAVIGATION.load(Ordering::Relaxed) {
+            self.render_navigation_debug(world);
+        }
+    }
+
+    /// Mark the blocked cells of `grid` on screen, and every waypoint of `paths`
+    fn render_navigation(&mut self, grid: &NavGrid, paths: &[Vec<Coord>], offset: (f64, f64)) {
+        let center = self.center();
+        let (gw, gh) = (self.width() as f64, self.height() as f64);
+        let screen = |pos: Coord| (center.0 + pos.x - offset.0, center.1 + pos.y - offset.1);
+
+        let first = grid.cell_of(Coord { x: offset.0 - center.0, y: offset.1 - center.1, z: 0. });
+        let (c0, r0) = first.unwrap_or((0, 0));
+        for row in r0..grid.rows {
+            for column in c0..grid.columns {
+                let (x, y) = screen(grid.cell_center(column, row));
+                if x > gw || y > gh {
+                    break;
+                }
+                let color = if grid.is_blocked(column, row) { (160, 32, 32) } else { (32, 64, 32) };
+                self.fill_rect_clipped(x - 1., y - 1., 2., 2., color);
+            }
+        }
+
+        for path in paths.iter() {
+            for waypoint in path.iter() {
+                let (x, y) = screen(*waypoint);
+                self.fill_rect_clipped(x - 2., y - 2., 4., 4., (255, 220, 0));
+            }
         }
-        self.render_entity(&world.player.entity, world.scroll);
     }
+
+    /// Where `render_navigation_debug()` keeps its grid between frames, with the obstacle
+    /// revision it was built at
+    ///
+    /// Without one, the grid is built again every frame.
+    fn navigation_grid(&mut self) -> Option<&mut Option<(usize, NavGrid)>> {
+        None
+    }
+
+    /// Navigation grid of the first player's size, with the paths every `Ai` entity would
+    /// take to the closest player
+    fn render_navigation_debug(&mut self, world: &World) {
+        let c = &world.components;
+        let hitbox = world.player()
+            .and_then(|id| c.collider.get(id))
+            .map_or(Region::default_hitbox(), |collider| collider.hitbox);
+        let cell_size = navigation::DEFAULT_CELL_SIZE;
+        let revision = world.obstacle_revision();
+        let grid = match self.navigation_grid().and_then(|cached| cached.take()) {
+            Some((built, grid)) if built == revision && grid.cell_size == cell_size && grid.hitbox == hitbox => grid,
+            _ => NavGrid::build(world, cell_size, hitbox),
+        };
+
+        let players: Vec<Coord> = world.players().filter_map(|id| c.position.get(id).copied()).collect();
+        let distance = |a: Coord, b: Coord| (a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y);
+        let paths: Vec<Vec<Coord>> = c.ai
+            .ids()
+            .filter_map(|id| {
+                let from = *c.position.get(id)?;
+                let to = players.iter().copied().min_by(|a, b| {
+                    distance(from, *a).partial_cmp(&distance(from, *b)).unwrap_or(core::cmp::Ordering::Equal)
+                })?;
+                grid.find_path(from, to)
+            })
+            .collect();
+
+        self.render_navigation(&grid, &paths, world.scroll);
+        if let Some(cached) = self.navigation_grid() {
+            *cached = Some((revision, grid));
+        }
+    }
+}
+
+/// Draw the navigation grid and the paths of `Ai` entities over the world
+pub static SHOW_NAVIGATION: AtomicBool = AtomicBool::new(false);
+
+pub fn toggle_navigation() {
+    SHOW_NAVIGATION.fetch_xor(true, Ordering::Relaxed);
 }
 
+/// Number of players that can share the keyboard
+pub const MAX_PLAYERS: usize = 2;
+
+/// Something a player can do with a key
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Jump,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct MovementKeys {
     pub up: bool,
     pub down: bool,
@@ -115,10 +457,34 @@ pub struct MovementKeys {
     pub jump: bool
 }
 
-pub static mut MOVEMENT_KEYS: MovementKeys = MovementKeys {
-    up: false,
-    down: false,
-    left: false,
-    right: false,
-    jump: false,
-};
\ No newline at end of file
+impl MovementKeys {
+    pub const fn new() -> Self {
+        Self {
+            up: false,
+            down: false,
+            left: false,
+            right: false,
+            jump: false,
+        }
+    }
+
+    pub fn set(&mut self, action: Action, pressed: bool) {
+        match action {
+            Action::Up => self.up = pressed,
+            Action::Down => self.down = pressed,
+            Action::Left => self.left = pressed,
+            Action::Right => self.right = pressed,
+            Action::Jump => self.jump = pressed,
+        }
+    }
+}
+
+/// Held keys of every input mapping, indexed by `PlayerControlled::input`
+pub static mut MOVEMENT_KEYS: [MovementKeys; MAX_PLAYERS] = [MovementKeys::new(); MAX_PLAYERS];
+
+/// Let go of every key in `MOVEMENT_KEYS`, when key releases can no longer be seen
+pub fn release_keys() {
+    unsafe {
+        MOVEMENT_KEYS = [MovementKeys::new(); MAX_PLAYERS];
+    }
+}
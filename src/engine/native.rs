@@ -1,21 +1,69 @@
 use super::*;
+use crate::assets::{reload_asset, watch::AssetWatcher, Asset, AssetSource};
+use crate::scene::{Command, Input, SceneStack};
+use futures::executor::block_on;
 use gameloop::{FrameAction, GameLoop};
-use minifb::{Key, Window, WindowOptions};
+use std::{sync::{atomic::Ordering, Arc}, time::Instant};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
 
 const INITIAL_WIDTH: usize = 640;
 const INITIAL_HEIGHT: usize = 480;
 
+/// Input mapping and action of a key, WASD for the first player and arrows for the second
+///
+/// Enter is left for `Command::Confirm`, so menus do not make the second player jump.
+fn binding(key: Key) -> Option<(usize, Action)> {
+    match key {
+        Key::W => Some((0, Action::Up)),
+        Key::S => Some((0, Action::Down)),
+        Key::A => Some((0, Action::Left)),
+        Key::D => Some((0, Action::Right)),
+        Key::Space => Some((0, Action::Jump)),
+        Key::Up => Some((1, Action::Up)),
+        Key::Down => Some((1, Action::Down)),
+        Key::Left => Some((1, Action::Left)),
+        Key::Right => Some((1, Action::Right)),
+        Key::RightShift => Some((1, Action::Jump)),
+        _ => None,
+    }
+}
+
 fn game_pixel_to_window_pixel(pixel: Pixel) -> u32 {
     ((pixel.0 as u32) << 16) +
     ((pixel.1 as u32) << 8) +
     pixel.2 as u32
 }
 
+fn reload_changed_assets(watcher: &mut AssetWatcher, world: &mut crate::world::World) {
+    let changed = match watcher.poll() {
+        Ok(changed) => changed,
+        Err(e) => {
+            println!("Could not reload asset index: {}", e);
+            return;
+        }
+    };
+    for asset in changed {
+        println!("Reloading asset: {:?}", asset);
+        if let Err(e) = block_on(reload_asset(watcher.source(), &asset, world)) {
+            println!("{}", e);
+        }
+    }
+}
+
 pub struct NativeEngine {
     pub world: crate::world::World,
+    /// Updated every tick and drawn every frame, the game ends when it is empty
+    pub scenes: SceneStack,
     pub window: Window,
     buffer: Vec<Pixel>,
     game_loop: GameLoop,
+    watcher: Option<AssetWatcher>,
+    mouse_down: bool,
+    focused: bool,
+    started: Instant,
+    stats: DebugStats,
+    font: Arc<Font>,
+    navigation: Option<(usize, NavGrid)>,
 }
 
 impl NativeEngine {
@@ -34,31 +82,46 @@ impl NativeEngine {
         window.limit_update_rate(None);
 
         Self {
-            world: world,
-            window: window,
+            world,
+            scenes: SceneStack::game(),
+            window,
             buffer: vec![(0, 0, 0); INITIAL_WIDTH * INITIAL_HEIGHT],
             game_loop: GameLoop::new(60, 10).expect("Failed to init game_loop"),
+            watcher: None,
+            mouse_down: false,
+            focused: true,
+            started: Instant::now(),
+            stats: DebugStats::new(),
+            font: Arc::new(Font::builtin()),
+            navigation: None,
         }
     }
 
-    fn dump(&self, _interpolation: f64) {
-        #[cfg(feature = "dump_log")]
-        println!(
-            "x: {:3.3} y: {:3.3} z: {:3.3} scroll: {:3.3} {:3.3} frame interpolation: {:1.3} w: {} h: {}",
-            self.world.player.entity.pos.x,
-            self.world.player.entity.pos.y,
-            self.world.player.entity.pos.z,
-            self.world.scroll.0,
-            self.world.scroll.1,
-            _interpolation,
-            self.width(),
-            self.height(),
-        );
+    /// Reload assets in `index` when they change, if `source` is a directory
+    pub fn watch_assets(&mut self, source: &AssetSource, index: Vec<Asset>) {
+        self.watcher = AssetWatcher::new(source, index);
+    }
+
+    /// Draw a loading screen right away, while the world is not ready to be shown
+    pub fn show_loading(&mut self, fraction: f64) {
+        self.render_loading(fraction);
+        self.present();
+    }
+
+    fn present(&mut self) {
+        let buf: Vec<u32> = self.buffer.iter().map(|p| { game_pixel_to_window_pixel(*p) }).collect();
+        self.window
+            .update_with_buffer(&buf, self.width(), self.height())
+            .unwrap();
+    }
+
+    /// Milliseconds since the engine was created
+    fn now(&self) -> f64 {
+        self.started.elapsed().as_secs_f64() * 1000.
     }
 
     pub fn engine_loop(&mut self) {
         let mut needs_render = false;
-        let mut interpolation = 0.0;
         loop {
             if needs_render {
                 needs_render = false;
@@ -67,46 +130,82 @@ impl NativeEngine {
 
                 // FIXME: this clone is nasty
                 let current_world = self.world.clone();
-                self.render_world(&current_world);
+                let mut scenes = std::mem::take(&mut self.scenes);
+                scenes.render(&current_world, self);
+                self.scenes = scenes;
 
-                self.dump(interpolation);
-                let buf: Vec<u32> = self.buffer.iter().map(|p| { game_pixel_to_window_pixel(*p) }).collect();
-                self.window
-                    .update_with_buffer(&buf, self.width(), self.height())
-                    .unwrap();
+                self.stats.frame(self.now());
+                if SHOW_DEBUG.load(Ordering::Relaxed) {
+                    let font = self.font.clone();
+                    let stats = self.stats;
+                    self.render_debug(&current_world, &stats, &font);
+                }
+                self.present();
             }
 
             for action in self.game_loop.actions() {
                 match action {
-                    FrameAction::Render { interpolation: i } => {
+                    FrameAction::Render { .. } => {
                         needs_render = true;
-                        interpolation = i;
                     }
 
                     FrameAction::Tick => {
-                        if !self.window.is_open() || self.window.is_key_down(Key::Escape) {
+                        if !self.window.is_open() || self.scenes.is_empty() {
                             return;
                         }
-                        if let Some(keys) = self.window.get_keys() {
-                            unsafe {
-                                if keys.contains(&Key::W) { MOVEMENT_KEYS.up = true; }
-                                else { MOVEMENT_KEYS.up = false; }
-
-                                if keys.contains(&Key::S) { MOVEMENT_KEYS.down = true; }
-                                else { MOVEMENT_KEYS.down = false; }
+                        let size = self.window.get_size();
+                        let mut input = Input::new((size.0 as f64, size.1 as f64));
 
-                                if keys.contains(&Key::A) { MOVEMENT_KEYS.left = true; }
-                                else { MOVEMENT_KEYS.left = false; }
-
-                                if keys.contains(&Key::D) { MOVEMENT_KEYS.right = true; }
-                                else { MOVEMENT_KEYS.right = false; }
+                        // Keys released while the window is in the background are never seen
+                        let focused = self.window.is_active();
+                        if focused != self.focused {
+                            self.focused = focused;
+                            release_keys();
+                            self.mouse_down = false;
+                            if focused {
+                                self.stats.restart();
+                            } else {
+                                input.focus_lost = true;
+                            }
+                        }
+                        if self.window.is_key_pressed(Key::Escape, KeyRepeat::No)
+                            || self.window.is_key_pressed(Key::P, KeyRepeat::No) {
+                            input.commands.push(Command::Pause);
+                        }
+                        if self.window.is_key_pressed(Key::Enter, KeyRepeat::No) {
+                            input.commands.push(Command::Confirm);
+                        }
+                        if self.window.is_key_pressed(Key::N, KeyRepeat::No) {
+                            toggle_navigation();
+                        }
+                        if self.window.is_key_pressed(Key::F3, KeyRepeat::No) {
+                            toggle_debug();
+                        }
+                        if let Some(keys) = self.window.get_keys().filter(|_| focused) {
+                            let mut held = [MovementKeys::new(); MAX_PLAYERS];
+                            for (input, action) in keys.into_iter().filter_map(binding) {
+                                held[input].set(action, true);
+                            }
+                            unsafe {
+                                MOVEMENT_KEYS = held;
+                            }
+                        }
 
-                                if keys.contains(&Key::Space) { MOVEMENT_KEYS.jump = true; }
-                                else { MOVEMENT_KEYS.jump = false; }
+                        // Clicks are when the left mouse button goes down
+                        let mouse_down = focused && self.window.get_mouse_down(MouseButton::Left);
+                        if mouse_down && !self.mouse_down {
+                            if let Some(pos) = self.window.get_mouse_pos(MouseMode::Discard) {
+                                input.clicks.push((pos.0 as f64, pos.1 as f64));
                             }
                         }
+                        self.mouse_down = mouse_down;
 
-                        self.world.tick();
+                        if let Some(watcher) = &mut self.watcher {
+                            reload_changed_assets(watcher, &mut self.world);
+                        }
+                        let start = self.now();
+                        self.scenes.update(&mut self.world, &input);
+                        self.stats.tick(start, self.now());
                     }
                 }
             }
@@ -157,4 +256,7 @@ impl Engine for NativeEngine {
             (result.blue  * 255.) as u8
         ));
     }
+    fn navigation_grid(&mut self) -> Option<&mut Option<(usize, NavGrid)>> {
+        Some(&mut self.navigation)
+    }
 }
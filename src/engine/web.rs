@@ -1,12 +1,30 @@
 use super::*;
 use crate::wasm_utils::*;
+use crate::scene::{Command, Input, SceneStack};
+use core::sync::atomic::{AtomicBool, Ordering};
 use wasm_bindgen::prelude::*;
 
+/// Pointer presses on the canvas, in canvas pixels, until the next `engine_cycle()`
+static CLICKS: std::sync::Mutex<Vec<(f64, f64)>> = std::sync::Mutex::new(Vec::new());
+
+/// Commands pressed until the next `engine_cycle()`
+static COMMANDS: std::sync::Mutex<Vec<Command>> = std::sync::Mutex::new(Vec::new());
+
+/// The page was hidden or lost focus since the last `engine_cycle()`
+static FOCUS_LOST: AtomicBool = AtomicBool::new(false);
+
+/// The page came back since the last `engine_cycle()`
+static FOCUS_GAINED: AtomicBool = AtomicBool::new(false);
+
 pub struct WebEngine {
     ctx: web_sys::CanvasRenderingContext2d,
     canvas: web_sys::HtmlCanvasElement,
-    ftime: f64,
+    stats: DebugStats,
+    font: std::sync::Arc<Font>,
+    navigation: Option<(usize, NavGrid)>,
     pub world: crate::world::World,
+    /// Updated and drawn every cycle
+    pub scenes: SceneStack,
 }
 
 unsafe impl Send for WebEngine {}
@@ -16,47 +34,45 @@ impl WebEngine {
         WebEngine {
             ctx: context(),
             canvas: canvas(),
-            ftime: 0.,
+            stats: DebugStats::new(),
+            font: std::sync::Arc::new(Font::builtin()),
+            navigation: None,
             world: world,
+            scenes: SceneStack::game(),
         }
     }
-    fn dump(&self) {
-        #[cfg(feature = "dump_log")]
-        self.ctx
-            .fill_text(
-                &format!(
-                    "pos: x: {:3.3} y: {:3.3} z: {:3.3} scroll: {:3.3}, {:3.3}",
-                    self.world.player.entity.pos.x,
-                    self.world.player.entity.pos.y,
-                    self.world.player.entity.pos.z,
-                    self.world.scroll.0,
-                    self.world.scroll.1
-                ),
-                10.,
-                30.,
-            )
-            .unwrap();
+    /// Draw a loading screen, shown as soon as the page gets to paint
+    pub fn show_loading(&mut self, fraction: f64) {
+        self.render_loading(fraction);
     }
+
     pub fn engine_cycle(&mut self) {
-        let frame_start = js_sys::Date::now();
+        let mut input = Input::new((self.canvas.width() as f64, self.canvas.height() as f64));
+        input.clicks = std::mem::take(&mut *CLICKS.lock().unwrap());
+        input.commands = std::mem::take(&mut *COMMANDS.lock().unwrap());
+        input.focus_lost = FOCUS_LOST.swap(false, Ordering::Relaxed);
+        if FOCUS_GAINED.swap(false, Ordering::Relaxed) {
+            // Animation frames stop while the page is hidden
+            self.stats.restart();
+        }
 
-        self.world.tick();
+        let tick_start = js_sys::Date::now();
+        self.scenes.update(&mut self.world, &input);
+        self.stats.tick(tick_start, js_sys::Date::now());
 
         self.world.scroll(self.center(), (self.canvas.width() as f64, self.canvas.height() as f64));
         self.clear();
         let current_world = self.world.clone();
-        self.render_world(&current_world);
-
-        self.ctx.set_fill_style(&"white".into());
-        self.ctx.set_font(&"10px monospace");
-        self.ctx
-            .fill_text(&format!("ftime: {}", self.ftime), 10., 10.)
-            .unwrap();
+        let mut scenes = std::mem::take(&mut self.scenes);
+        scenes.render(&current_world, self);
+        self.scenes = scenes;
 
-        self.dump();
-
-        let frame_end = js_sys::Date::now();
-        self.ftime = frame_end - frame_start;
+        self.stats.frame(js_sys::Date::now());
+        if SHOW_DEBUG.load(Ordering::Relaxed) {
+            let font = self.font.clone();
+            let stats = self.stats;
+            self.render_debug(&current_world, &stats, &font);
+        }
     }
 
 }
@@ -94,32 +110,91 @@ impl Engine for WebEngine {
         self.
         ctx.fill_rect(x as f64, y as f64, w as f64, h as f64);
     }
+    fn navigation_grid(&mut self) -> Option<&mut Option<(usize, NavGrid)>> {
+        Some(&mut self.navigation)
+    }
+}
+
+/// Input mapping and action of a key, WASD for the only player
+///
+/// The page has no way to add players, so there is no mapping for a second one.
+fn binding(key: &str) -> Option<(usize, Action)> {
+    match key {
+        "w" | "W" => Some((0, Action::Up)),
+        "a" | "A" => Some((0, Action::Left)),
+        "s" | "S" => Some((0, Action::Down)),
+        "d" | "D" => Some((0, Action::Right)),
+        " " | "Spacebar" => Some((0, Action::Jump)),
+        _ => None,
+    }
+}
+
+fn set_key(key: &str, pressed: bool) {
+    if let Some((input, action)) = binding(key) {
+        unsafe {
+            let mut keys = MOVEMENT_KEYS[input];
+            keys.set(action, pressed);
+            MOVEMENT_KEYS[input] = keys;
+        }
+    }
+}
+
+/// Command of a key, pressed once
+fn command(key: &str) -> Option<Command> {
+    match key {
+        "Escape" | "Esc" | "p" | "P" => Some(Command::Pause),
+        "Enter" => Some(Command::Confirm),
+        _ => None,
+    }
+}
+
+/// Keys used by the game, kept from doing what the browser does with them, like F3 searching
+fn is_handled(key: &str) -> bool {
+    matches!(key, "n" | "N" | "F3") || command(key).is_some() || binding(key).is_some()
 }
 
 #[wasm_bindgen]
 pub fn key_down(e: web_sys::KeyboardEvent) {
-    unsafe {
-        match e.key().as_str() {
-            "w" | "W" => MOVEMENT_KEYS.up = true,
-            "a" | "A" => MOVEMENT_KEYS.left = true,
-            "s" | "S" => MOVEMENT_KEYS.down = true,
-            "d" | "D" => MOVEMENT_KEYS.right = true,
-            " " | "Spacebar" => MOVEMENT_KEYS.jump = true,
+    let key = e.key();
+    if is_handled(&key) {
+        e.prevent_default();
+    }
+    if !e.repeat() {
+        match key.as_str() {
+            "n" | "N" => toggle_navigation(),
+            "F3" => toggle_debug(),
             _ => {}
         }
+        COMMANDS.lock().unwrap().extend(command(&key));
     }
+    set_key(&key, true);
 }
 
 #[wasm_bindgen]
 pub fn key_up(e: web_sys::KeyboardEvent) {
-    unsafe {
-        match e.key().as_str() {
-            "w" | "W" => MOVEMENT_KEYS.up = false,
-            "a" | "A" => MOVEMENT_KEYS.left = false,
-            "s" | "S" => MOVEMENT_KEYS.down = false,
-            "d" | "D" => MOVEMENT_KEYS.right = false,
-            " " | "Spacebar" => MOVEMENT_KEYS.jump = false,
-            _ => {}
-        }
+    if is_handled(&e.key()) {
+        e.prevent_default();
     }
+    set_key(e.key().as_str(), false);
+}
+
+/// Press at `(x, y)` in canvas pixels, not CSS pixels
+#[wasm_bindgen]
+pub fn pointer_down(x: f64, y: f64) {
+    CLICKS.lock().unwrap().push((x, y));
+}
+
+/// The page was hidden or lost focus, key releases will not be seen until it is back
+#[wasm_bindgen]
+pub fn focus_lost() {
+    release_keys();
+    CLICKS.lock().unwrap().clear();
+    COMMANDS.lock().unwrap().clear();
+    FOCUS_LOST.store(true, Ordering::Relaxed);
+}
+
+/// The page is visible and focused again
+#[wasm_bindgen]
+pub fn focus_gained() {
+    FOCUS_GAINED.store(true, Ordering::Relaxed);
 }
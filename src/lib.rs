@@ -1,4 +1,3 @@
-#![feature(type_ascription)]
 #![cfg_attr(feature = "no_std", no_std)]
 
 #[cfg(feature = "alloc")]
@@ -10,6 +9,18 @@ pub mod world;
 #[cfg(not(feature = "bare"))]
 pub mod assets;
 
+#[cfg(not(feature = "bare"))]
+pub mod save;
+
+#[cfg(not(feature = "bare"))]
+pub mod audio;
+
+#[cfg(not(feature = "bare"))]
+pub mod ui;
+
+#[cfg(not(feature = "bare"))]
+pub mod scene;
+
 #[cfg(target_arch = "wasm32")]
 mod wasm_utils;
 
@@ -28,25 +39,40 @@ use {
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
+#[cfg(target_arch = "wasm32")]
+async fn load_world_assets(
+    world: &mut world::World,
+    engine: &mut engine::web::WebEngine,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let source = AssetSource::find().await?;
+    let assets = load_assets(&source).await?;
+    log!("importing assets");
+    import_assets_with_progress(&source, assets, world, |progress| {
+        engine.show_loading(progress.fraction())
+    }).await
+}
+
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub async fn run() {
     wasm_utils::set_panic_hook();
-    let mut e = world::Entity::new();
-    e.pos = world::Coord {
+    let mut world = world::World::new();
+    world.spawn_body(world::Coord {
         x: 100.0,
         y: 200.0,
         z: 5.0,
-    };
-    let mut world = world::World::new();
-    world.entities.push(e);
+    });
 
-    log!("loading assets");
-    let assets = load_assets().await.unwrap();
-    log!("importing assets");
-    import_assets(assets, &mut world).await.unwrap();
+    // The canvas shows a loading screen until the world is ready
+    let mut engine = engine::web::WebEngine::new(world::World::new());
+    engine.show_loading(0.);
 
-    let mut engine = engine::web::WebEngine::new(world);
+    log!("loading assets");
+    if let Err(e) = load_world_assets(&mut world, &mut engine).await {
+        log!("{}", e);
+        return;
+    }
+    engine.world = world;
 
     let f = Rc::new(RefCell::new(None));
     let g = f.clone();
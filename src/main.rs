@@ -4,22 +4,45 @@ use std::error::Error;
 use futures::executor::block_on;
 
 async fn run() -> Result<(), Box<dyn Error>> {
-    use canvasgame_rust::{engine::native::NativeEngine, world::*, assets::*};
+    use canvasgame_rust::{engine::{native::NativeEngine, MAX_PLAYERS}, world::*, assets::*};
 
     let mut world = World::new();
-    let mut e = Entity::new();
-    e.pos = Coord {
+    let e = world.spawn_body(Coord {
         x: 100.0,
         y: 200.0,
         z: 5.0,
-    };
-    e.hitbox.start.z = 4.;
-    world.entities.push(e);
+    });
+    if let Some(collider) = world.components.collider.get_mut(e) {
+        collider.hitbox.start.z = 4.;
+    }
+
+    // `--players 2` for local co-op, the extra players start next to the first one
+    let players = std::env::args()
+        .skip_while(|arg| arg != "--players")
+        .nth(1)
+        .and_then(|n| n.parse::<usize>().ok())
+        .unwrap_or(1);
+    if players > MAX_PLAYERS {
+        eprintln!("At most {} players can share the keyboard, starting with {}", MAX_PLAYERS, MAX_PLAYERS);
+    }
+    for i in 1..players.min(MAX_PLAYERS) {
+        world.spawn_player(Coord { x: 48. * i as f64, y: 0., z: 0. });
+    }
 
-    let assets = load_assets()?;
-    import_assets(assets, &mut world).await?;
+    // `--assets PATH` for an asset directory or pack other than the one found by default
+    let source = match std::env::args().skip_while(|arg| arg != "--assets").nth(1) {
+        Some(path) => AssetSource::open(path)?,
+        None => AssetSource::find()?,
+    };
+    // The window shows a loading screen until the world is ready
+    let mut engine = NativeEngine::new(World::new());
+    let assets = load_assets(&source).await?;
+    import_assets_with_progress(&source, assets.clone(), &mut world, |progress| {
+        engine.show_loading(progress.fraction())
+    }).await?;
 
-    let mut engine = NativeEngine::new(world);
+    engine.world = world;
+    engine.watch_assets(&source, assets);
     engine.engine_loop();
 
     Ok(())
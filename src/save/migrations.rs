@@ -0,0 +1,169 @@
+//! Upgrading save documents written by older builds
+//!
+//! Every change to the saved types needs a new function at the end of `MIGRATIONS`, taking
+//! a document of the previous version to the next one, and a fixture of the old format in
+//! `tests/fixtures`. Migrations work on plain JSON, so they never depend on the current
+//! shape of `World`.
+
+use super::invalid;
+
+use serde_json::{json, Map, Value};
+use std::error::Error;
+
+pub type Migration = fn(Value) -> Result<Value, Box<dyn Error>>;
+
+/// `MIGRATIONS[n]` upgrades a document from version `n + 1` to `n + 2`
+pub const MIGRATIONS: &[Migration] = &[v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7];
+
+/// Schema version of a save document
+///
+/// Version 1 stored it at the top level, later versions in the header.
+pub fn version(document: &Value) -> Option<u32> {
+    document
+        .pointer("/header/version")
+        .or_else(|| document.get("version"))
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+}
+
+/// Upgrade `document` to `SAVE_VERSION` step by step
+pub fn migrate(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let mut current = version(&document).ok_or_else(|| invalid("missing version"))?;
+    if current == 0 || current > super::SAVE_VERSION {
+        return Err(invalid(format!("unsupported version {}", current)));
+    }
+
+    while current < super::SAVE_VERSION {
+        document = MIGRATIONS[current as usize - 1](document)?;
+
+        let next = version(&document);
+        if next != Some(current + 1) {
+            return Err(invalid(format!("migration from version {} did not advance", current)));
+        }
+        current += 1;
+    }
+    Ok(document)
+}
+
+/// Move the version into a separate header
+fn v1_to_v2(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let object = document.as_object_mut().ok_or_else(|| invalid("not an object"))?;
+    object.remove("version");
+    object.insert("header".to_string(), json!({ "version": 2 }));
+    Ok(document)
+}
+
+/// Split the player and entity structs into component storages
+fn v2_to_v3(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let world = document
+        .get_mut("world")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| invalid("missing world"))?;
+
+    let player = world.remove("player").and_then(|mut p| p.get_mut("entity").map(Value::take));
+    let entities = match world.remove("entities") {
+        Some(Value::Array(entities)) => entities,
+        _ => Vec::new(),
+    };
+
+    let mut ids = Vec::new();
+    let (mut position, mut velocity, mut collider) = (Map::new(), Map::new(), Map::new());
+    let (mut sprite, mut players, mut properties) = (Map::new(), Map::new(), Map::new());
+
+    let all = player.iter().map(|p| (true, p)).chain(entities.iter().map(|e| (false, e)));
+    for (is_player, entity) in all {
+        let id = entity.get("id").and_then(Value::as_u64).ok_or_else(|| invalid("entity without id"))?;
+        let key = id.to_string();
+        ids.push(json!(id));
+
+        position.insert(key.clone(), entity["pos"].clone());
+        velocity.insert(key.clone(), entity["vel"].clone());
+        collider.insert(key.clone(), json!({
+            "hitbox": entity["hitbox"],
+            "mass": entity["mass"],
+            "pushable": entity["pushable"],
+            "solid": entity["solid"],
+        }));
+        if !entity["texture_path"].is_null() {
+            sprite.insert(key.clone(), json!({ "path": entity["texture_path"] }));
+        }
+        if entity["properties"].as_object().is_some_and(|p| !p.is_empty()) {
+            properties.insert(key.clone(), entity["properties"].clone());
+        }
+        if is_player {
+            players.insert(key, json!({}));
+        }
+    }
+
+    world.insert("entities".to_string(), Value::Array(ids));
+    world.insert("components".to_string(), json!({
+        "position": position,
+        "velocity": velocity,
+        "collider": collider,
+        "sprite": sprite,
+        "player": players,
+        "properties": properties,
+    }));
+
+    document["header"]["version"] = json!(3);
+    Ok(document)
+}
+
+/// Give every player its own key mapping, in the order of their ids
+fn v3_to_v4(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let players = document
+        .pointer_mut("/world/components/player")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| invalid("missing player components"))?;
+
+    let mut ids: Vec<(u64, String)> = players
+        .keys()
+        .map(|key| key.parse().map(|id| (id, key.clone())))
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid("player id is not a number"))?;
+    ids.sort();
+    for (input, (_, key)) in ids.into_iter().enumerate() {
+        players.insert(key, json!({ "input": input }));
+    }
+
+    document["header"]["version"] = json!(4);
+    Ok(document)
+}
+
+/// Add the storage of `Ai` components
+fn v4_to_v5(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let components = document
+        .pointer_mut("/world/components")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| invalid("missing components"))?;
+    components.insert("ai".to_string(), json!({}));
+
+    document["header"]["version"] = json!(5);
+    Ok(document)
+}
+
+/// Add the storage of `Script` components
+fn v5_to_v6(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let components = document
+        .pointer_mut("/world/components")
+        .and_then(Value::as_object_mut)
+        .ok_or_else(|| invalid("missing components"))?;
+    components.insert("script".to_string(), json!({}));
+
+    document["header"]["version"] = json!(6);
+    Ok(document)
+}
+
+/// Tilemaps remember the level they came from, older ones from none
+fn v6_to_v7(mut document: Value) -> Result<Value, Box<dyn Error>> {
+    let tilemaps = document
+        .pointer_mut("/world/tilemaps")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| invalid("missing tilemaps"))?;
+    for tilemap in tilemaps.iter_mut().filter_map(Value::as_object_mut) {
+        tilemap.insert("source".to_string(), Value::Null);
+    }
+
+    document["header"]["version"] = json!(7);
+    Ok(document)
+}
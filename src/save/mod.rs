@@ -0,0 +1,161 @@
+//! Persisting the `World` between sessions
+//!
+//! Textures are saved as asset paths only, `restore()` takes the pixel data from the
+//! currently loaded `World` again. Saves written by older builds are upgraded through
+//! `migrations` before being read.
+
+use crate::world::{entity::claim_id, World};
+
+pub mod migrations;
+
+use image::RgbaImage;
+use serde::{Serialize, Deserialize};
+use std::{collections::HashMap, fmt, error::Error};
+
+#[cfg(feature = "native")]
+use std::fs;
+
+/// Version of the save format written by this build
+pub const SAVE_VERSION: u32 = migrations::MIGRATIONS.len() as u32 + 1;
+
+#[cfg(feature = "native")]
+const SAVE_DIR: &str = "./saves";
+
+#[cfg(target_arch = "wasm32")]
+const STORAGE_PREFIX: &str = "canvasgame.save.";
+
+#[derive(Debug)]
+pub struct SaveError {
+    reason: String,
+}
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not load save: {}", self.reason)
+    }
+}
+impl Error for SaveError {}
+
+fn invalid(reason: impl Into<String>) -> Box<dyn Error> {
+    Box::new(SaveError { reason: reason.into() })
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SaveHeader {
+    /// Schema version of the rest of the document
+    pub version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    header: SaveHeader,
+    world: World,
+}
+
+pub fn serialize(world: &World) -> Result<Vec<u8>, Box<dyn Error>> {
+    let save = SaveFile {
+        header: SaveHeader { version: SAVE_VERSION },
+        world: world.clone(),
+    };
+    Ok(serde_json::to_vec(&save)?)
+}
+
+/// Read a saved `World` of any known version, without any textures
+pub fn deserialize(data: &[u8]) -> Result<World, Box<dyn Error>> {
+    let document = migrations::migrate(serde_json::from_slice(data)?)?;
+    let save: SaveFile = serde_json::from_value(document)?;
+
+    let world = save.world;
+    for id in world.entities.iter() {
+        claim_id(*id);
+    }
+    Ok(world)
+}
+
+/// Replace `world` with `saved`, reusing the textures of `world` by their asset paths
+pub fn restore(world: &mut World, mut saved: World) {
+    let mut textures: HashMap<String, RgbaImage> = HashMap::new();
+    for (_, sprite) in world.components.sprite.iter_mut() {
+        if let (Some(path), Some(texture)) = (sprite.path.take(), sprite.texture.take()) {
+            textures.insert(path, texture);
+        }
+    }
+    for tilemap in world.tilemaps.iter_mut() {
+        if let (Some(path), Some(tileset)) = (tilemap.tileset_path.take(), tilemap.tileset.take()) {
+            textures.insert(path, tileset);
+        }
+    }
+
+    for (_, sprite) in saved.components.sprite.iter_mut() {
+        if let Some(path) = &sprite.path {
+            // Keep the saved hitbox, the texture only sets it on first import
+            sprite.texture = textures.get(path).cloned();
+        }
+    }
+    for tilemap in saved.tilemaps.iter_mut() {
+        if let Some(path) = &tilemap.tileset_path {
+            tilemap.tileset = textures.get(path).cloned();
+        }
+    }
+
+    // Systems and scripts are code, sounds and fonts are too big and the UI is not game state,
+    // they are not saved
+    saved.systems = world.systems.clone();
+    saved.sounds = std::mem::take(&mut world.sounds);
+    saved.fonts = std::mem::take(&mut world.fonts);
+    saved.ui = std::mem::take(&mut world.ui);
+    #[cfg(feature = "rhai")]
+    {
+        saved.scripts = std::mem::take(&mut world.scripts);
+    }
+    *world = saved;
+}
+
+/// Slot names become file names, so they may not reach outside the save directory
+fn check_name(name: &str) -> Result<(), Box<dyn Error>> {
+    if name.is_empty() || name.contains(&['/', '\\', '\0'][..]) || name.contains("..") {
+        return Err(invalid(format!("bad save name {:?}", name)));
+    }
+    Ok(())
+}
+
+/// Save `world` to the slot called `name`
+pub fn save(world: &World, name: &str) -> Result<(), Box<dyn Error>> {
+    check_name(name)?;
+    let data = serialize(world)?;
+
+    #[cfg(feature = "native")]
+    {
+        fs::create_dir_all(SAVE_DIR)?;
+        fs::write(format!("{}/{}.json", SAVE_DIR, name), data)?;
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let key = format!("{}{}", STORAGE_PREFIX, name);
+        crate::wasm_utils::save_state(&key, &String::from_utf8(data)?)
+            .map_err(|reason| SaveError { reason })?;
+    }
+
+    Ok(())
+}
+
+/// Load the slot called `name` into `world`
+pub fn load(world: &mut World, name: &str) -> Result<(), Box<dyn Error>> {
+    check_name(name)?;
+
+    #[cfg(feature = "native")]
+    let data = fs::read(format!("{}/{}.json", SAVE_DIR, name))?;
+
+    #[cfg(target_arch = "wasm32")]
+    let data = {
+        let key = format!("{}{}", STORAGE_PREFIX, name);
+        match crate::wasm_utils::load_state(&key).map_err(|reason| SaveError { reason })? {
+            Some(data) => data.into_bytes(),
+            None => return Err(invalid(format!("no save called {}", name))),
+        }
+    };
+
+    let saved = deserialize(&data)?;
+    restore(world, saved);
+    Ok(())
+}
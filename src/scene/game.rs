@@ -0,0 +1,172 @@
+//! Scenes of the game itself
+
+use super::{Command, Input, Scene, Transition};
+use crate::{
+    engine::Engine,
+    ui::{Anchor, Ui, Widget, WidgetKind},
+    world::World,
+};
+
+const BUTTON_SIZE: (f64, f64) = (96., 20.);
+
+/// Buttons clicked in `ui` this tick
+fn clicks(ui: &Ui, world: &World, input: &Input) -> Vec<String> {
+    input.clicks.iter().filter_map(|pos| ui.click(*pos, input.screen, &world.fonts)).collect()
+}
+
+/// Menu in the middle of the screen, with a title above its buttons
+fn menu(title: &str, buttons: &[(&str, &str)]) -> Ui {
+    let mut ui = Ui::new();
+    let height = 40. + buttons.len() as f64 * (BUTTON_SIZE.1 + 8.);
+    ui.add(Widget::panel((16, 16, 32)).at(Anchor::Center, (0., 0.)).sized(160., height));
+    let label = WidgetKind::Label { text: title.into(), font: None, scale: 2, color: (255, 255, 255) };
+    ui.add(Widget::new(label).at(Anchor::Center, (0., 16. - height / 2.)));
+    for (i, (id, text)) in buttons.iter().enumerate() {
+        let y = 40. - height / 2. + i as f64 * (BUTTON_SIZE.1 + 8.) + BUTTON_SIZE.1 / 2.;
+        ui.add(Widget::button(*text).with_id(*id).at(Anchor::Center, (0., y)).sized(BUTTON_SIZE.0, BUTTON_SIZE.1));
+    }
+    ui
+}
+
+/// The world standing still behind a menu, started with Enter
+pub struct Title {
+    ui: Ui,
+}
+
+impl Default for Title {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Title {
+    pub fn new() -> Self {
+        #[cfg(feature = "native")]
+        let buttons = [("start", "Start"), ("quit", "Quit")];
+        #[cfg(not(feature = "native"))]
+        let buttons = [("start", "Start")];
+        Self { ui: menu("CanvasGame", &buttons) }
+    }
+}
+
+impl Scene for Title {
+    fn update(&mut self, world: &mut World, input: &Input) -> Transition {
+        let clicks = clicks(&self.ui, world, input);
+        if input.pressed(Command::Confirm) || clicks.iter().any(|id| id == "start") {
+            Transition::Replace(Box::new(Gameplay::new()))
+        } else if clicks.iter().any(|id| id == "quit") {
+            Transition::Quit
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, world: &World, engine: &mut dyn Engine) {
+        engine.render_world(world);
+        engine.render_widgets(&self.ui, &world.fonts);
+    }
+}
+
+/// The world running, until every player is gone
+pub struct Gameplay {
+    /// The world as it was when the game started, to start over from
+    start: Option<World>,
+}
+
+impl Default for Gameplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gameplay {
+    pub fn new() -> Self {
+        Self { start: None }
+    }
+}
+
+impl Scene for Gameplay {
+    fn update(&mut self, world: &mut World, input: &Input) -> Transition {
+        let start = self.start.get_or_insert_with(|| world.clone());
+        if input.pressed(Command::Pause) || input.focus_lost {
+            return Transition::Push(Box::new(Pause::new()));
+        }
+        world.tick();
+
+        if world.players().next().is_none() {
+            return Transition::Replace(Box::new(GameOver::new(start.clone())));
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, world: &World, engine: &mut dyn Engine) {
+        engine.render_world(world);
+        engine.render_ui(world);
+    }
+}
+
+/// Menu over the frozen game, closed with Escape, P or Enter
+pub struct Pause {
+    ui: Ui,
+}
+
+impl Default for Pause {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Pause {
+    pub fn new() -> Self {
+        Self { ui: menu("Paused", &[("resume", "Resume")]) }
+    }
+}
+
+impl Scene for Pause {
+    fn update(&mut self, world: &mut World, input: &Input) -> Transition {
+        let clicks = clicks(&self.ui, world, input);
+        if input.pressed(Command::Pause) || input.pressed(Command::Confirm) || clicks.iter().any(|id| id == "resume") {
+            Transition::Pop
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, world: &World, engine: &mut dyn Engine) {
+        engine.render_widgets(&self.ui, &world.fonts);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+/// Shown when every player is gone, going back to the title screen with the world reset
+pub struct GameOver {
+    start: World,
+    ui: Ui,
+}
+
+impl GameOver {
+    /// `start` is the world to go back to
+    pub fn new(start: World) -> Self {
+        Self { start, ui: menu("Game over", &[("restart", "Restart")]) }
+    }
+}
+
+impl Scene for GameOver {
+    fn update(&mut self, world: &mut World, input: &Input) -> Transition {
+        let clicks = clicks(&self.ui, world, input);
+        if input.pressed(Command::Confirm) || clicks.iter().any(|id| id == "restart") {
+            *world = self.start.clone();
+            Transition::Replace(Box::new(Title::new()))
+        } else {
+            Transition::None
+        }
+    }
+
+    fn render(&mut self, world: &World, engine: &mut dyn Engine) {
+        engine.render_world(world);
+        engine.render_widgets(&self.ui, &world.fonts);
+    }
+}
@@ -0,0 +1,134 @@
+//! Screens of the game on a stack, like the title screen, gameplay and the pause menu
+//!
+//! The engines own a `SceneStack` and hand it input every tick and the screen every frame.
+//! Only the scene on top is updated, so a pause menu pushed over gameplay freezes
+//! `World::tick()`, while overlays let the scenes below them be drawn. Gameplay pauses by
+//! itself when the game loses focus.
+
+pub mod game;
+
+pub use game::{GameOver, Gameplay, Pause, Title};
+
+use crate::{engine::Engine, world::World};
+
+/// Input that is not held, but pressed once
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    /// Escape or P
+    Pause,
+    /// Enter
+    Confirm,
+}
+
+/// What happened since the last tick
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Input {
+    pub commands: Vec<Command>,
+    /// Pointer presses in screen pixels
+    pub clicks: Vec<(f64, f64)>,
+    /// Size of the screen the clicks are on
+    pub screen: (f64, f64),
+    /// The window lost focus or the page was hidden, held keys were released
+    pub focus_lost: bool,
+}
+
+impl Input {
+    pub fn new(screen: (f64, f64)) -> Self {
+        Self { screen, ..Self::default() }
+    }
+
+    pub fn pressed(&self, command: Command) -> bool {
+        self.commands.contains(&command)
+    }
+}
+
+/// Change of the stack, returned by `Scene::update()`
+pub enum Transition {
+    None,
+    /// Put a scene on top, the current one waits below it
+    Push(Box<dyn Scene>),
+    /// Remove the current scene, going back to the one below
+    Pop,
+    /// Swap the current scene for another one
+    Replace(Box<dyn Scene>),
+    /// Remove every scene, ending the game
+    Quit,
+}
+
+pub trait Scene {
+    /// Called every tick while the scene is on top of the stack
+    fn update(&mut self, world: &mut World, input: &Input) -> Transition;
+
+    fn render(&mut self, world: &World, engine: &mut dyn Engine);
+
+    /// The scenes below are drawn first, like for a menu over the game
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Default)]
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl SceneStack {
+    pub fn new(first: Box<dyn Scene>) -> Self {
+        Self { scenes: vec![first] }
+    }
+
+    /// Title screen, leading to gameplay
+    pub fn game() -> Self {
+        Self::new(Box::new(Title::new()))
+    }
+
+    pub fn push(&mut self, scene: Box<dyn Scene>) {
+        self.scenes.push(scene);
+    }
+
+    pub fn pop(&mut self) -> Option<Box<dyn Scene>> {
+        self.scenes.pop()
+    }
+
+    pub fn replace(&mut self, scene: Box<dyn Scene>) -> Option<Box<dyn Scene>> {
+        let old = self.scenes.pop();
+        self.scenes.push(scene);
+        old
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    /// The game is over once every scene is gone
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    /// Update the scene on top, then apply the transition it asks for
+    pub fn update(&mut self, world: &mut World, input: &Input) {
+        let transition = match self.scenes.last_mut() {
+            Some(scene) => scene.update(world, input),
+            None => return,
+        };
+        match transition {
+            Transition::None => {}
+            Transition::Push(scene) => self.push(scene),
+            Transition::Pop => {
+                self.pop();
+            }
+            Transition::Replace(scene) => {
+                self.replace(scene);
+            }
+            Transition::Quit => self.scenes.clear(),
+        }
+    }
+
+    /// Draw the scene on top, and the ones below it as long as they are covered by overlays
+    pub fn render(&mut self, world: &World, engine: &mut dyn Engine) {
+        let first = self.scenes.iter().rposition(|scene| !scene.is_overlay()).unwrap_or(0);
+        for scene in self.scenes[first..].iter_mut() {
+            scene.render(world, engine);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! Widgets drawn over the world in screen space, like a HUD or menus
+//!
+//! Widgets are kept in `World::ui` and drawn by `Engine::render_ui()` after the world. They
+//! are placed relative to an `Anchor` of the screen every frame, so they follow resizes.
+//! They are added from code, or from the JSON layout of a `Layout` asset.
+
+pub mod widget;
+
+pub use widget::{Anchor, Widget, WidgetKind};
+
+use crate::engine::Font;
+
+use image::RgbaImage;
+use serde::{Serialize, Deserialize};
+use std::{collections::BTreeMap, error::Error, sync::Arc};
+
+/// Space between the text of a button and its border
+const BUTTON_PADDING: f64 = 4.;
+
+/// Widgets of a layout asset, in drawing order
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Layout {
+    pub widgets: Vec<Widget>,
+}
+
+impl Layout {
+    pub fn from_slice(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_slice(data)?)
+    }
+
+    /// Make icon paths relative to the asset directory, instead of the layout at `path`
+    pub fn relative_to(&mut self, path: &str) {
+        if let Some(i) = path.rfind('/') {
+            for widget in self.widgets.iter_mut() {
+                if let WidgetKind::Icon { image } = &mut widget.kind {
+                    *image = format!("{}/{}", &path[..i], image);
+                }
+            }
+        }
+    }
+
+    /// Every image used by an icon, once
+    pub fn images(&self) -> Vec<&str> {
+        let mut images: Vec<&str> = Vec::new();
+        for widget in self.widgets.iter() {
+            if let WidgetKind::Icon { image } = &widget.kind {
+                if !images.contains(&image.as_str()) {
+                    images.push(image);
+                }
+            }
+        }
+        images
+    }
+}
+
+/// Every widget on screen, and what they need to be drawn
+#[derive(Clone, Debug)]
+pub struct Ui {
+    /// In drawing order, later widgets are on top
+    pub widgets: Vec<Widget>,
+    /// Icon images by asset path
+    pub images: BTreeMap<String, Arc<RgbaImage>>,
+    /// Used by buttons, and by labels without a font
+    pub font: Arc<Font>,
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ui {
+    pub fn new() -> Self {
+        Self {
+            widgets: Vec::new(),
+            images: BTreeMap::new(),
+            font: Arc::new(Font::builtin()),
+        }
+    }
+
+    pub fn add(&mut self, widget: Widget) {
+        self.widgets.push(widget);
+    }
+
+    /// Replace every widget with the ones of `layout`
+    pub fn set_layout(&mut self, layout: Layout) {
+        self.widgets = layout.widgets;
+    }
+
+    /// The first widget called `id`
+    pub fn get(&self, id: &str) -> Option<&Widget> {
+        self.widgets.iter().find(|w| w.id.as_deref() == Some(id))
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        self.widgets.iter_mut().find(|w| w.id.as_deref() == Some(id))
+    }
+
+    /// Change the text of the label or button called `id`
+    pub fn set_text(&mut self, id: &str, new: impl Into<String>) {
+        match self.get_mut(id).map(|w| &mut w.kind) {
+            Some(WidgetKind::Label { text, .. }) | Some(WidgetKind::Button { text, .. }) => *text = new.into(),
+            _ => {}
+        }
+    }
+
+    /// Change the value of the bar called `id`
+    pub fn set_value(&mut self, id: &str, new: f64) {
+        if let Some(WidgetKind::Bar { value, .. }) = self.get_mut(id).map(|w| &mut w.kind) {
+            *value = new;
+        }
+    }
+
+    /// Font of a label, falling back to `font` if it was not imported
+    pub fn font_of<'a>(&'a self, name: Option<&str>, fonts: &'a BTreeMap<String, Arc<Font>>) -> &'a Font {
+        name.and_then(|name| fonts.get(name)).unwrap_or(&self.font)
+    }
+
+    /// Size of `widget` on screen, `Widget::size` or the size of its content
+    pub fn size_of(&self, widget: &Widget, fonts: &BTreeMap<String, Arc<Font>>) -> (f64, f64) {
+        if widget.size != (0., 0.) {
+            return widget.size;
+        }
+        match &widget.kind {
+            WidgetKind::Label { text, font, scale, .. } => {
+                let (w, h) = self.font_of(font.as_deref(), fonts).measure(text);
+                ((w * scale) as f64, (h * scale) as f64)
+            }
+            WidgetKind::Icon { image } => self.images.get(image)
+                .map_or((0., 0.), |image| (image.width() as f64, image.height() as f64)),
+            WidgetKind::Button { text, .. } => {
+                let (w, h) = self.font.measure(text);
+                (w as f64 + 2. * BUTTON_PADDING, h as f64 + 2. * BUTTON_PADDING)
+            }
+            _ => (0., 0.),
+        }
+    }
+
+    /// `(x, y, w, h)` of `widget` on a `screen` big screen
+    pub fn rect(&self, widget: &Widget, screen: (f64, f64), fonts: &BTreeMap<String, Arc<Font>>) -> (f64, f64, f64, f64) {
+        let size = self.size_of(widget, fonts);
+        let (x, y) = widget.place(size, screen);
+        (x, y, size.0, size.1)
+    }
+
+    /// Press the pointer at `pos` on screen, returning the ID of the topmost button hit
+    pub fn click(&self, pos: (f64, f64), screen: (f64, f64), fonts: &BTreeMap<String, Arc<Font>>) -> Option<String> {
+        self.widgets.iter().rev()
+            .filter(|w| w.visible && matches!(w.kind, WidgetKind::Button { .. }))
+            .find(|w| {
+                let (x, y, width, height) = self.rect(w, screen, fonts);
+                pos.0 >= x && pos.0 < x + width && pos.1 >= y && pos.1 < y + height
+            })
+            .and_then(|w| w.id.clone())
+    }
+}
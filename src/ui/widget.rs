@@ -0,0 +1,189 @@
+//! Widget types and their placement on screen
+
+use crate::engine::Pixel;
+
+use serde::{Serialize, Deserialize};
+
+/// Point of the screen a widget is placed relative to, and the same point of the widget
+///
+/// A widget anchored `BottomRight` with an offset of `(-8, -8)` has its bottom right corner
+/// 8 pixels away from the bottom right corner of the screen, whatever the screen size.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Position of the anchor as a fraction of the width and height of a rectangle
+    pub fn fraction(self) -> (f64, f64) {
+        match self {
+            Anchor::TopLeft => (0., 0.),
+            Anchor::Top => (0.5, 0.),
+            Anchor::TopRight => (1., 0.),
+            Anchor::Left => (0., 0.5),
+            Anchor::Center => (0.5, 0.5),
+            Anchor::Right => (1., 0.5),
+            Anchor::BottomLeft => (0., 1.),
+            Anchor::Bottom => (0.5, 1.),
+            Anchor::BottomRight => (1., 1.),
+        }
+    }
+}
+
+/// What a widget shows
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum WidgetKind {
+    /// Text in a font asset, or the builtin font
+    Label {
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        font: Option<String>,
+        #[serde(default = "default_scale")]
+        scale: usize,
+        #[serde(default = "default_text_color")]
+        color: Pixel,
+    },
+    /// Health bar, or any other amount, filled to `value` out of `max`
+    Bar {
+        value: f64,
+        max: f64,
+        #[serde(default = "default_bar_color")]
+        color: Pixel,
+        #[serde(default)]
+        background: Pixel,
+    },
+    /// Image from `Ui::images`, by asset path
+    Icon { image: String },
+    /// Plain rectangle, usually behind other widgets
+    Panel {
+        color: Pixel,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        border: Option<Pixel>,
+    },
+    /// Box with text, reported by `Ui::click()`
+    Button {
+        text: String,
+        #[serde(default = "default_button_color")]
+        color: Pixel,
+        #[serde(default = "default_text_color")]
+        text_color: Pixel,
+    },
+}
+
+fn default_scale() -> usize {
+    1
+}
+
+fn default_text_color() -> Pixel {
+    (255, 255, 255)
+}
+
+fn default_bar_color() -> Pixel {
+    (200, 32, 32)
+}
+
+fn default_button_color() -> Pixel {
+    (64, 64, 96)
+}
+
+fn visible() -> bool {
+    true
+}
+
+/// Element of the UI, placed in screen space
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Widget {
+    /// Name for game code to find the widget by, and for clicks on buttons
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub anchor: Anchor,
+    /// Distance from the anchor, in pixels
+    #[serde(default)]
+    pub offset: (f64, f64),
+    /// Labels and icons are as big as their content if this is zero
+    #[serde(default)]
+    pub size: (f64, f64),
+    #[serde(default = "visible")]
+    pub visible: bool,
+    #[serde(flatten)]
+    pub kind: WidgetKind,
+}
+
+impl Widget {
+    /// Visible widget in the top left corner, sized to its content
+    pub fn new(kind: WidgetKind) -> Self {
+        Self {
+            id: None,
+            anchor: Anchor::default(),
+            offset: (0., 0.),
+            size: (0., 0.),
+            visible: true,
+            kind,
+        }
+    }
+
+    pub fn label(text: impl Into<String>) -> Self {
+        Self::new(WidgetKind::Label {
+            text: text.into(),
+            font: None,
+            scale: default_scale(),
+            color: default_text_color(),
+        })
+    }
+
+    pub fn bar(value: f64, max: f64) -> Self {
+        Self::new(WidgetKind::Bar { value, max, color: default_bar_color(), background: (0, 0, 0) })
+    }
+
+    pub fn icon(image: impl Into<String>) -> Self {
+        Self::new(WidgetKind::Icon { image: image.into() })
+    }
+
+    pub fn panel(color: Pixel) -> Self {
+        Self::new(WidgetKind::Panel { color, border: None })
+    }
+
+    pub fn button(text: impl Into<String>) -> Self {
+        Self::new(WidgetKind::Button {
+            text: text.into(),
+            color: default_button_color(),
+            text_color: default_text_color(),
+        })
+    }
+
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn at(mut self, anchor: Anchor, offset: (f64, f64)) -> Self {
+        self.anchor = anchor;
+        self.offset = offset;
+        self
+    }
+
+    pub fn sized(mut self, w: f64, h: f64) -> Self {
+        self.size = (w, h);
+        self
+    }
+
+    /// Top left corner of the widget, if it is `size` big on a `screen` big screen
+    pub fn place(&self, size: (f64, f64), screen: (f64, f64)) -> (f64, f64) {
+        let (fx, fy) = self.anchor.fraction();
+        (
+            fx * (screen.0 - size.0) + self.offset.0,
+            fy * (screen.1 - size.1) + self.offset.1,
+        )
+    }
+}
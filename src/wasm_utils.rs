@@ -1,6 +1,8 @@
+use js_sys::{ArrayBuffer, Uint8Array};
+use std::{error::Error, fmt};
 use wasm_bindgen::{prelude::*, JsCast};
-use image::DynamicImage;
-use serde::Deserialize;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::Response;
 
 pub fn set_panic_hook() {
     // When the `console_error_panic_hook` feature is enabled, we can call the
@@ -60,58 +62,70 @@ macro_rules! log {
     }
 }
 
-//#[link(wasm_import_module = "../www/assets.js")]
 #[wasm_bindgen]
 extern {
-    #[wasm_bindgen(js_namespace = window)]
-    async fn load_asset_index_inner() -> JsValue;
-
-    #[wasm_bindgen(js_namespace = window)]
-    async fn load_asset_file_inner(ptr: *const u8, len: usize) -> JsValue;
+    /// The asset index bundled with the page, as UTF-8 JSON in a `Uint8Array`
+    #[wasm_bindgen(js_namespace = window, catch)]
+    async fn load_asset_index_inner() -> Result<JsValue, JsValue>;
 }
 
 #[wasm_bindgen]
-pub fn alloc(len: usize) -> *mut u8 {
-    let mut buf = Vec::with_capacity(len);
-    let ptr = buf.as_mut_ptr();
-    std::mem::forget(buf);
-    ptr
-}
+extern {
+    #[wasm_bindgen(js_namespace = window, catch)]
+    fn save_state_inner(key: &str, data: &str) -> Result<(), JsValue>;
 
-#[derive(Deserialize, Debug)]
-struct PtrLen {
-    ptr: usize,
-    len: usize
+    #[wasm_bindgen(js_namespace = window, catch)]
+    fn load_state_inner(key: &str) -> Result<Option<String>, JsValue>;
 }
 
-fn jsval_to_ptrlen(val: JsValue) -> (*mut u8, usize) {
-    let obj: PtrLen = val.into_serde().unwrap();
-    (obj.ptr as *mut u8, obj.len)
+pub fn save_state(key: &str, data: &str) -> Result<(), String> {
+    save_state_inner(key, data).map_err(|e| format!("{:?}", e))
 }
 
-fn asset_data(val: JsValue) -> Vec<u8> {
-    let (ptr, len) = jsval_to_ptrlen(val);
-    let data = unsafe { Vec::from_raw_parts(ptr, len, len) };
-    data
+pub fn load_state(key: &str) -> Result<Option<String>, String> {
+    load_state_inner(key).map_err(|e| format!("{:?}", e))
 }
 
+#[derive(Debug)]
+pub struct FetchError {
+    url: String,
+    reason: String,
+}
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Could not fetch {}: {}", self.url, self.reason)
+    }
+}
+impl Error for FetchError {}
 
-pub async fn load_asset_index() -> Vec<u8> {
-    let val = load_asset_index_inner().await;
-    asset_data(val)
+fn js_error(url: &str, e: JsValue) -> Box<dyn Error> {
+    let reason = e.as_string().unwrap_or_else(|| format!("{:?}", e));
+    Box::new(FetchError { url: url.to_string(), reason })
 }
 
-pub async fn load_asset_file(ptr: *const u8, len: usize) -> Vec<u8> {
-    let val = load_asset_file_inner(ptr, len).await;
-    asset_data(val)
+/// Bytes of a `Uint8Array` or `ArrayBuffer`
+fn bytes(url: &str, value: JsValue) -> Result<Vec<u8>, Box<dyn Error>> {
+    if value.is_instance_of::<Uint8Array>() || value.is_instance_of::<ArrayBuffer>() {
+        Ok(Uint8Array::new(&value).to_vec())
+    } else {
+        Err(js_error(url, "not binary data".into()))
+    }
 }
 
-pub fn load_image_from_array(buf: &[u8]) -> DynamicImage {
-    let result = image::load_from_memory(buf);
-    match result {
-        Err(e) => {
-            panic!("failed to load image: {}", e);
-        }
-        Ok(img) => { return img; }
+/// Content of the file at `path`, relative to the website root
+pub async fn fetch(path: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Relative to the page, so the game also works below the root of a site
+    let url = path.trim_start_matches('/');
+    let response = JsFuture::from(window().fetch_with_str(url)).await.map_err(|e| js_error(url, e))?;
+    let response: Response = response.dyn_into().map_err(|e| js_error(url, e))?;
+    if !response.ok() {
+        return Err(js_error(url, format!("HTTP status {}", response.status()).into()));
     }
+    let buffer = response.array_buffer().map_err(|e| js_error(url, e))?;
+    bytes(url, JsFuture::from(buffer).await.map_err(|e| js_error(url, e))?)
+}
+
+pub async fn load_asset_index() -> Result<Vec<u8>, Box<dyn Error>> {
+    let index = load_asset_index_inner().await.map_err(|e| js_error("index.json", e))?;
+    bytes("index.json", index)
 }
@@ -0,0 +1,174 @@
+//! Behaviours that steer non-player entities
+//!
+//! An entity with an `Ai` component accelerates like a player holding the keys its behaviour
+//! picks, so it is limited by the same `Velocity::max` and collides the same way.
+
+use super::*;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Distance at which a patrol waypoint counts as reached
+const ARRIVE_DISTANCE: f64 = 8.;
+/// Ticks between direction changes while wandering
+const WANDER_TICKS: u32 = 90;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Behaviour {
+    /// Stand still
+    Idle,
+    /// Walk to each of `waypoints` in turn, starting over after the last one
+    Patrol { waypoints: Vec<Coord>, next: usize },
+    /// Walk towards the closest player within `radius`
+    Chase { radius: f64 },
+    /// Walk away from the closest player within `radius`
+    Flee { radius: f64 },
+    /// Walk in a random direction, picking a new one every now and then
+    Wander { seed: u32, direction: (f64, f64), ticks: u32 },
+}
+
+/// Lets an entity move on its own
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ai {
+    pub behaviour: Behaviour,
+}
+
+impl Ai {
+    pub fn new(behaviour: Behaviour) -> Self {
+        Self { behaviour }
+    }
+
+    /// Behaviour described by level data
+    ///
+    /// `ai` is one of `patrol`, `chase`, `flee` or `wander`. Chasing and fleeing react to
+    /// players within `radius` (default 200). Patrols follow `waypoints`, given as
+    /// `x,y x,y ...` in world coordinates.
+    pub fn from_properties(properties: &Properties) -> Option<Ai> {
+        let radius = properties.get("radius").and_then(|r| r.parse().ok()).unwrap_or(200.);
+        let behaviour = match properties.get("ai")?.as_str() {
+            "idle" => Behaviour::Idle,
+            "patrol" => Behaviour::Patrol {
+                waypoints: properties.get("waypoints").map(|w| parse_waypoints(w)).unwrap_or_default(),
+                next: 0,
+            },
+            "chase" => Behaviour::Chase { radius },
+            "flee" => Behaviour::Flee { radius },
+            "wander" => Behaviour::Wander {
+                seed: properties.get("seed").and_then(|s| s.parse().ok()).unwrap_or(1),
+                direction: (0., 0.),
+                ticks: 0,
+            },
+            _ => return None,
+        };
+        Some(Ai::new(behaviour))
+    }
+}
+
+/// Points separated by whitespace, skipping anything that is not an `x,y` pair
+fn parse_waypoints(text: &str) -> Vec<Coord> {
+    text.split_whitespace()
+        .filter_map(|point| {
+            let (x, y) = point.split_once(',')?;
+            Some(Coord { x: x.trim().parse().ok()?, y: y.trim().parse().ok()?, z: 0. })
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "no_std"))]
+#[inline]
+fn sqrt(value: f64) -> f64 {
+    value.sqrt()
+}
+
+#[cfg(feature = "no_std")]
+#[inline]
+fn sqrt(value: f64) -> f64 {
+    libm::sqrt(value)
+}
+
+/// Distance on the ground, ignoring height
+fn distance(a: Coord, b: Coord) -> f64 {
+    sqrt((a.x - b.x) * (a.x - b.x) + (a.y - b.y) * (a.y - b.y))
+}
+
+/// Unit vector from `from` towards `to`, or nothing if they are at the same spot
+fn direction(from: Coord, to: Coord) -> (f64, f64) {
+    let length = distance(from, to);
+    if length == 0. {
+        return (0., 0.);
+    }
+    ((to.x - from.x) / length, (to.y - from.y) / length)
+}
+
+/// Next step of a xorshift generator, never returns 0 for a non-zero `state`
+fn xorshift(mut state: u32) -> u32 {
+    state ^= state << 13;
+    state ^= state >> 17;
+    state ^= state << 5;
+    state
+}
+
+/// Pick the direction each `Ai` entity wants to go, and accelerate towards it
+pub fn ai(world: &mut World) {
+    let c = &mut world.components;
+    let players: Vec<Position> = c.player.ids().filter_map(|id| c.position.get(id).copied()).collect();
+
+    for (id, ai) in c.ai.iter_mut() {
+        let pos = match c.position.get(id) {
+            Some(pos) => *pos,
+            None => continue,
+        };
+        let closest = players
+            .iter()
+            .map(|p| (distance(pos, *p), *p))
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+
+        let (dx, dy) = match &mut ai.behaviour {
+            Behaviour::Idle => (0., 0.),
+            Behaviour::Patrol { waypoints, next } => {
+                if waypoints.is_empty() {
+                    (0., 0.)
+                } else {
+                    *next %= waypoints.len();
+                    if distance(pos, waypoints[*next]) <= ARRIVE_DISTANCE {
+                        *next = (*next + 1) % waypoints.len();
+                    }
+                    direction(pos, waypoints[*next])
+                }
+            }
+            Behaviour::Chase { radius } => match closest {
+                Some((d, player)) if d <= *radius => direction(pos, player),
+                _ => (0., 0.),
+            },
+            Behaviour::Flee { radius } => match closest {
+                Some((d, player)) if d <= *radius => direction(player, pos),
+                _ => (0., 0.),
+            },
+            Behaviour::Wander { seed, direction, ticks } => {
+                if *ticks == 0 {
+                    *seed = xorshift((*seed).max(1));
+                    // One in four turns is spent standing still
+                    *direction = match *seed % 4 {
+                        0 => (0., 0.),
+                        _ => {
+                            let angle = (*seed >> 2) % 8;
+                            let diagonal = core::f64::consts::FRAC_1_SQRT_2;
+                            [(1., 0.), (diagonal, diagonal), (0., 1.), (-diagonal, diagonal),
+                             (-1., 0.), (-diagonal, -diagonal), (0., -1.), (diagonal, -diagonal)][angle as usize]
+                        }
+                    };
+                    *ticks = WANDER_TICKS;
+                }
+                *ticks -= 1;
+                *direction
+            }
+        };
+
+        if let Some(vel) = c.velocity.get_mut(id) {
+            vel.to.x += dx * vel.falloff.x * 2.;
+            vel.to.y += dy * vel.falloff.y * 2.;
+        }
+    }
+}
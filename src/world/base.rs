@@ -1,5 +1,6 @@
 /// Coordinate relative to middle of screen
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coord {
     /// Horizontal offset
     /// 
@@ -93,6 +94,7 @@ impl core::ops::Sub for Coord {
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Region {
     /// Top left coordinate relative to origin
     /// 
@@ -130,7 +132,55 @@ impl Region {
     }
 }
 
+/// How a `Velocity` loses speed on the horizontal axes
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Damping {
+    /// Subtract `falloff` from the speed every tick
+    Linear,
+    /// Keep `1 - falloff` of the speed every tick
+    Exponential,
+}
+
+impl Damping {
+    /// Damp a single axis by `falloff` over `dt` ticks
+    ///
+    /// The result never has a different sign than `value`, it stops at zero.
+    pub fn apply(&self, value: f64, falloff: f64, dt: f64) -> f64 {
+        let dt = dt.max(0.);
+        match self {
+            Damping::Linear => {
+                let amount = falloff.abs() * dt;
+                if value > 0. {
+                    (value - amount).max(0.)
+                } else if value < 0. {
+                    (value + amount).min(0.)
+                } else {
+                    0.
+                }
+            }
+            Damping::Exponential => {
+                let keep = 1. - falloff.abs().min(1.);
+                value * powf(keep, dt)
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+#[inline]
+fn powf(base: f64, exp: f64) -> f64 {
+    base.powf(exp)
+}
+
+#[cfg(feature = "no_std")]
+#[inline]
+fn powf(base: f64, exp: f64) -> f64 {
+    libm::pow(base, exp)
+}
+
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Indicates a direction in movement
 pub struct Velocity {
     /// Movement relative to current position
@@ -138,7 +188,17 @@ pub struct Velocity {
     /// Maximum velocity per axis
     pub max: Coord,
     /// Velocity falloff per tick, if not moving
+    ///
+    /// The `z` axis is pulled down by this amount instead (gravity).
     pub falloff: Coord,
+    /// Damping model used for `falloff` on the `x` and `y` axes
+    pub damping: Damping,
+}
+
+impl Default for Velocity {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Velocity {
@@ -147,6 +207,7 @@ impl Velocity {
             to: Coord::origin(),
             max: Coord::default_max_vel(),
             falloff: Coord::default_vel_falloff(),
+            damping: Damping::Linear,
         }
     }
     /// Limit `to` to `max`
@@ -160,14 +221,15 @@ impl Velocity {
         // if self.to.z > self.max.z { self.to.z = self.max.z }
         // if self.to.z < -self.max.z { self.to.z = -self.max.z }
     }
-    /// Apply `falloff` values
+    /// Apply `falloff` values for a single tick
     pub fn do_falloff(&mut self) {
-        if self.to.x > 0. { self.to.x -= self.falloff.x }
-        if self.to.x < 0. { self.to.x += self.falloff.x }
-        
-        if self.to.y > 0. { self.to.y -= self.falloff.y }
-        if self.to.y < 0. { self.to.y += self.falloff.y }
+        self.damp(1.);
+    }
+    /// Apply `falloff` values scaled to a time step of `dt` ticks
+    pub fn damp(&mut self, dt: f64) {
+        self.to.x = self.damping.apply(self.to.x, self.falloff.x, dt);
+        self.to.y = self.damping.apply(self.to.y, self.falloff.y, dt);
 
-        self.to.z -= self.falloff.z;
+        self.to.z -= self.falloff.z * dt.max(0.);
     }
 }
@@ -0,0 +1,343 @@
+use super::*;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// Passes over every pair per tick, so pushes can propagate through a chain of entities
+const RESOLVE_PASSES: usize = 4;
+
+/// Height tolerance when deciding whether an entity passes above another one
+const COLLISION_THRESHOLD: f64 = 4.;
+
+/// Entities closer than this are still touching, so resting against a wall is a contact
+const CONTACT_SKIN: f64 = 1e-6;
+
+/// Overlap between two entities, along the axis of least penetration
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    /// Unit vector pointing from the first entity towards the second one
+    pub normal: Coord,
+    /// Penetration depth along `normal`, zero or below if they only touch
+    pub depth: f64,
+}
+
+impl Contact {
+    /// Find the overlap between `a` and `b`, if they touch
+    ///
+    /// Entities passing above each other (e.g. while jumping) do not touch.
+    fn between(a: &Body, b: &Body) -> Option<Contact> {
+        let (ra, rb) = (a.absolute_pos(), b.absolute_pos());
+        if is_above(a, &rb) || is_above(b, &ra) {
+            return None;
+        }
+        Contact::of_regions(&ra, &rb)
+    }
+
+    /// Find the overlap between two regions on the horizontal plane, if they touch
+    pub fn of_regions(ra: &Region, rb: &Region) -> Option<Contact> {
+        let (dx, dy) = overlap(ra, rb);
+        if dx < -CONTACT_SKIN || dy < -CONTACT_SKIN {
+            return None;
+        }
+
+        let (ca, cb) = (center(ra), center(rb));
+        let normal = if dx < dy {
+            Coord { x: if ca.x <= cb.x { 1. } else { -1. }, y: 0., z: 0. }
+        } else {
+            Coord { x: 0., y: if ca.y <= cb.y { 1. } else { -1. }, z: 0. }
+        };
+
+        Some(Contact { normal, depth: dx.min(dy) })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CollisionPhase {
+    /// The entities started touching this tick
+    Begin,
+    /// The entities were already touching in the previous tick
+    Stay,
+    /// The entities stopped touching this tick
+    End,
+}
+
+/// Something game code can react to after `World::tick()`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollisionEvent {
+    pub phase: CollisionPhase,
+    /// Always the smaller of the two IDs
+    pub a: EntityId,
+    pub b: EntityId,
+    /// Contact normal pointing from `a` towards `b`, as of the last touch
+    pub normal: Coord,
+    /// One of the entities is not `solid`, so neither was blocked
+    pub trigger: bool,
+}
+
+/// Copy of the components of an entity that collision works with
+#[derive(Clone, Copy)]
+struct Body {
+    id: EntityId,
+    pos: Position,
+    /// Zero for entities without a `Velocity`
+    vel: Coord,
+    collider: Collider,
+    /// Landed on top of another entity during this tick
+    resting: bool,
+}
+
+impl Body {
+    fn absolute_pos(&self) -> Region {
+        self.collider.absolute(self.pos)
+    }
+}
+
+/// A pair of entities touching during a tick
+#[derive(Clone, Copy, Debug)]
+pub(in crate::world) struct Touch {
+    a: EntityId,
+    b: EntityId,
+    normal: Coord,
+    trigger: bool,
+}
+
+impl Touch {
+    fn new(a: &Body, b: &Body, contact: &Contact) -> Touch {
+        let trigger = !a.collider.solid || !b.collider.solid;
+        if a.id <= b.id {
+            Touch { a: a.id, b: b.id, normal: contact.normal, trigger }
+        } else {
+            let n = contact.normal;
+            Touch { a: b.id, b: a.id, normal: Coord { x: -n.x, y: -n.y, z: -n.z }, trigger }
+        }
+    }
+
+    fn same_pair(&self, other: &Touch) -> bool {
+        self.a == other.a && self.b == other.b
+    }
+
+    fn event(&self, phase: CollisionPhase) -> CollisionEvent {
+        CollisionEvent { phase, a: self.a, b: self.b, normal: self.normal, trigger: self.trigger }
+    }
+}
+
+/// Compare the touching pairs of two consecutive ticks
+pub(in crate::world) fn events(previous: &[Touch], current: &[Touch]) -> Vec<CollisionEvent> {
+    let mut events = Vec::new();
+    for touch in current.iter() {
+        let phase = if previous.iter().any(|t| t.same_pair(touch)) {
+            CollisionPhase::Stay
+        } else {
+            CollisionPhase::Begin
+        };
+        events.push(touch.event(phase));
+    }
+    for touch in previous.iter() {
+        if !current.iter().any(|t| t.same_pair(touch)) {
+            events.push(touch.event(CollisionPhase::End));
+        }
+    }
+    events
+}
+
+fn is_above(body: &Body, other: &Region) -> bool {
+    body.pos.z + body.collider.hitbox.end.z - COLLISION_THRESHOLD >= other.start.z
+}
+
+/// Overlap of two regions per axis (x, y), negative if apart
+fn overlap(ra: &Region, rb: &Region) -> (f64, f64) {
+    (
+        ra.end.x.min(rb.end.x) - ra.start.x.max(rb.start.x),
+        ra.end.y.min(rb.end.y) - ra.start.y.max(rb.start.y),
+    )
+}
+
+fn center(region: &Region) -> Coord {
+    Coord {
+        x: (region.start.x + region.end.x) / 2.,
+        y: (region.start.y + region.end.y) / 2.,
+        z: 0.,
+    }
+}
+
+fn along(v: Coord, normal: Coord) -> f64 {
+    v.x * normal.x + v.y * normal.y
+}
+
+/// How easily `body` gives way in a collision
+///
+/// Pushable entities always give way, others only if they moved into the contact
+/// themselves. Heavier entities give way less.
+fn give(body: &Body, approach: f64) -> f64 {
+    let collider = &body.collider;
+    if (collider.pushable || approach > 0.) && collider.mass > 0. {
+        1. / collider.mass
+    } else {
+        0.
+    }
+}
+
+/// Stop `body` falling into `other` from above, so it stands on top of it
+///
+/// Returns whether `body` is on top of `other`, in which case it is not pushed aside.
+/// Bodies that were already below the top of `other` before falling this tick are beside it.
+fn land(body: &mut Body, other: &Region) -> bool {
+    let bottom_before = body.pos.z - body.vel.z + body.collider.hitbox.end.z;
+    if body.vel.z > 0. || bottom_before < other.start.z - COLLISION_THRESHOLD {
+        return false;
+    }
+    if body.vel.z < 0. {
+        body.pos.z -= body.vel.z;
+        body.vel.z = 0.;
+    }
+    body.resting = true;
+    true
+}
+
+fn resolve_pair(a: &mut Body, b: &mut Body, touching: &mut Vec<Touch>) {
+    let contact = match Contact::between(a, b) {
+        Some(c) => c,
+        None => return,
+    };
+
+    let touch = Touch::new(a, b, &contact);
+    if !touching.iter().any(|t| t.same_pair(&touch)) {
+        touching.push(touch);
+    }
+    if touch.trigger || contact.depth <= 0. {
+        return;
+    }
+    let (ra, rb) = (a.absolute_pos(), b.absolute_pos());
+    if land(a, &rb) || land(b, &ra) {
+        return;
+    }
+
+    let n = contact.normal;
+
+    let (va, vb) = (along(a.vel, n), along(b.vel, n));
+    let (ga, gb) = (give(a, va), give(b, -vb));
+    if ga + gb == 0. {
+        return;
+    }
+    let (sa, sb) = (ga / (ga + gb), gb / (ga + gb));
+
+    // Separate the pair
+    a.pos.x -= n.x * contact.depth * sa;
+    a.pos.y -= n.y * contact.depth * sa;
+    b.pos.x += n.x * contact.depth * sb;
+    b.pos.y += n.y * contact.depth * sb;
+
+    // Cancel the closing speed, the heavier entity keeps more of its own
+    let closing = va - vb;
+    if closing > 0. {
+        a.vel.x -= n.x * closing * sa;
+        a.vel.y -= n.y * closing * sa;
+        b.vel.x += n.x * closing * sb;
+        b.vel.y += n.y * closing * sb;
+    }
+}
+
+/// Push `entity` out of the solid tiles of `tilemap`, as if they were static entities
+fn resolve_tiles(entity: &mut Body, tilemap: &Tilemap) {
+    if !entity.collider.solid {
+        return;
+    }
+
+    // Deepest overlaps first, so sliding along a wall does not catch on the seams between tiles
+    let region = entity.absolute_pos();
+    let (columns, rows) = tilemap.cells_in(region);
+    let mut cells: Vec<(Region, f64)> = Vec::new();
+    for row in rows {
+        for column in columns.clone() {
+            if !tilemap.is_solid(column, row) {
+                continue;
+            }
+            let cell = tilemap.cell_region(column, row);
+            let (dx, dy) = overlap(&region, &cell);
+            if dx > 0. && dy > 0. {
+                cells.push((cell, dx * dy));
+            }
+        }
+    }
+    cells.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(core::cmp::Ordering::Equal));
+
+    for (cell, _) in cells.iter() {
+        if is_above(entity, cell) {
+            continue;
+        }
+        let contact = match Contact::of_regions(&entity.absolute_pos(), cell) {
+            Some(c) if c.depth > 0. => c,
+            _ => continue,
+        };
+        let n = contact.normal;
+
+        entity.pos.x -= n.x * contact.depth;
+        entity.pos.y -= n.y * contact.depth;
+
+        let v = along(entity.vel, n);
+        if v > 0. {
+            entity.vel.x -= n.x * v;
+            entity.vel.y -= n.y * v;
+        }
+    }
+}
+
+/// Resolve collisions between every pair of `bodies`, all of them possibly moving, and
+/// against the solid tiles of `tilemaps`
+///
+/// Returns every pair of entities that touched along the way.
+fn resolve(bodies: &mut [Body], tilemaps: &[Tilemap]) -> Vec<Touch> {
+    let mut touching = Vec::new();
+    for _ in 0..RESOLVE_PASSES {
+        for i in 0..bodies.len() {
+            let (head, tail) = bodies.split_at_mut(i + 1);
+            let a = &mut head[i];
+            for b in tail.iter_mut() {
+                resolve_pair(a, b, &mut touching);
+            }
+        }
+        for body in bodies.iter_mut() {
+            for tilemap in tilemaps.iter() {
+                resolve_tiles(body, tilemap);
+            }
+        }
+    }
+    touching
+}
+
+/// Resolve collisions of every entity with a `Position` and a `Collider`
+pub fn collision(world: &mut World) {
+    let c = &world.components;
+    let mut bodies: Vec<Body> = c.collider.with(&c.position)
+        .map(|(id, collider, pos)| Body {
+            id,
+            pos: *pos,
+            vel: c.velocity.get(id).map_or(Coord::origin(), |v| v.to),
+            collider: *collider,
+            resting: false,
+        })
+        .collect();
+
+    let touching = resolve(&mut bodies, &world.tilemaps);
+
+    let c = &mut world.components;
+    let mut moved = Vec::new();
+    for body in bodies.iter() {
+        if let Some(pos) = c.position.get_mut(body.id) {
+            if *pos != body.pos {
+                moved.push(body.id);
+            }
+            *pos = body.pos;
+        }
+        if let Some(vel) = c.velocity.get_mut(body.id) {
+            vel.to = body.vel;
+        }
+    }
+
+    if moved.into_iter().any(|id| navigation::is_obstacle(&world.components, id)) {
+        world.obstacles_changed();
+    }
+    world.resting = bodies.iter().filter(|b| b.resting).map(|b| b.id).collect();
+    world.events = events(&world.touching, &touching);
+    world.touching = touching;
+}
@@ -0,0 +1,97 @@
+use super::*;
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, string::String};
+#[cfg(not(feature = "alloc"))]
+use std::collections::BTreeMap;
+
+#[cfg(not(feature = "bare"))]
+use image::RgbaImage;
+
+/// Position of an entity relative to world origin
+pub type Position = Coord;
+
+/// Free-form values from level data, for game code to interpret
+pub type Properties = BTreeMap<String, String>;
+
+/// Solid shape of an entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collider {
+    /// Region relative to the entity's `Position`
+    pub hitbox: Region,
+    /// Relative weight when resolving collisions against other entities
+    pub mass: f64,
+    /// Other entities can shove this one around
+    pub pushable: bool,
+    /// Blocks other entities; non-solid entities only report overlaps (pickups, doors, ...)
+    pub solid: bool,
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Collider {
+    pub fn new() -> Self {
+        Self {
+            hitbox: Region::default_hitbox(),
+            mass: 1.,
+            pushable: false,
+            solid: true,
+        }
+    }
+
+    /// Returns the collider's region relative to the world
+    pub fn absolute(&self, pos: Position) -> Region {
+        Region {
+            start: pos + self.hitbox.start,
+            end: pos + self.hitbox.end,
+        }
+    }
+}
+
+/// Texture drawn at the entity's position
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sprite {
+    #[cfg(not(feature = "bare"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub texture: Option<RgbaImage>,
+    /// Asset path of `texture`, saved instead of the pixel data
+    pub path: Option<String>,
+}
+
+/// Every component type an entity can have
+///
+/// Adding a component type means adding a storage here, and removing from it in `remove()`.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Components {
+    pub position: Storage<Position>,
+    pub velocity: Storage<Velocity>,
+    pub collider: Storage<Collider>,
+    pub sprite: Storage<Sprite>,
+    pub player: Storage<PlayerControlled>,
+    pub ai: Storage<Ai>,
+    #[cfg(feature = "rhai")]
+    pub script: Storage<Script>,
+    pub properties: Storage<Properties>,
+}
+
+impl Components {
+    /// Drop every component of `id`
+    pub fn remove(&mut self, id: EntityId) {
+        self.position.remove(id);
+        self.velocity.remove(id);
+        self.collider.remove(id);
+        self.sprite.remove(id);
+        self.player.remove(id);
+        self.ai.remove(id);
+        #[cfg(feature = "rhai")]
+        self.script.remove(id);
+        self.properties.remove(id);
+    }
+}
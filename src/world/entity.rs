@@ -1,112 +1,84 @@
-use super::*;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
-#[cfg(not(feature = "bare"))]
-use image::RgbaImage;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "alloc"))]
+use std::collections::BTreeMap;
 
-#[derive(Clone)]
-pub struct Entity {
-    pub pos: Coord,
-    pub vel: Velocity,
-    pub(in crate::world) in_air: bool,
-    pub hitbox: Region,
+/// Unique identifier of an entity, the key of its components in every `Storage`
+pub type EntityId = usize;
 
-    #[cfg(not(feature = "bare"))]
-    pub texture: Option<RgbaImage>,
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+pub(in crate::world) fn next_id() -> EntityId {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
 }
 
-impl Entity {
-    pub fn new() -> Entity {
-        Entity {
-            pos: Coord::origin(),
-            hitbox: Region::default_hitbox(),
-            vel: Velocity::new(),
-            in_air: false,
+/// Make sure newly created entities never reuse `id`, e.g. after loading a saved `World`
+pub(crate) fn claim_id(id: EntityId) {
+    NEXT_ID.fetch_max(id + 1, Ordering::Relaxed);
+}
 
-            #[cfg(not(feature = "bare"))]
-            texture: None,
-        }
-    }
+/// Components of a single type, keyed by the entity they belong to
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+pub struct Storage<T> {
+    items: BTreeMap<EntityId, T>,
+}
 
-    /// Returns the `Entity`'s region relative to the world
-    pub(in crate::world) fn absolute_pos(&self) -> Region {
-        Region {
-            start: self.pos + self.hitbox.start,
-            end: self.pos + self.hitbox.end,
-        }
+impl<T> Default for Storage<T> {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    #[cfg(not(feature = "bare"))]
-    pub fn set_texture(&mut self, texture: Option<RgbaImage>) {
-        if let Some(bitmap) = texture {
-            let (w, h) = (bitmap.width(), bitmap.height());
-            self.hitbox.start.x = -(w as f64 / 2.);
-            self.hitbox.start.y = -(h as f64 / 2.);
-            self.hitbox.end.x = w as f64 / 2.;
-            self.hitbox.end.y = h as f64 / 2.;
-
-            self.texture = Some(bitmap);
-            return;
-        }
-        self.texture = None;
+impl<T> Storage<T> {
+    pub fn new() -> Self {
+        Self { items: BTreeMap::new() }
     }
 
-    fn collide(&mut self, collide_with: Vec<Region>) {
-        for other in collide_with.iter() {
-            const COLLISION_THRESHOLD: f64 = 4.;
-
-            if self.absolute_pos().overlaps(*other) {
-                if self.vel.to.z != 0. {
-                    if self.pos.z + self.hitbox.end.z - COLLISION_THRESHOLD >= other.start.z {
-                        continue;
-                    } else {
-                        //self.pos.z = other.start.y - self.hitbox.end.z;
-                        self.pos.z -= self.vel.to.z;
-                        self.vel.to.z = 0.;
-                        self.in_air = false;
-                    }
-                }
-
-                if self.vel.to.x > 0.
-                    && self.pos.x + self.hitbox.end.x - COLLISION_THRESHOLD <= other.start.x
-                {
-                    self.pos.x = other.start.x - self.hitbox.end.x;
-                    self.vel.to.x = 0.;
-                } else if self.vel.to.x < 0.
-                    && self.pos.x + self.hitbox.start.x + COLLISION_THRESHOLD >= other.end.x
-                {
-                    self.pos.x = other.end.x - self.hitbox.start.x;
-                    self.vel.to.x = 0.;
-                }
-
-                if self.vel.to.y > 0.
-                    && self.pos.y + self.hitbox.end.y - COLLISION_THRESHOLD <= other.start.y
-                {
-                    self.pos.y = other.start.y - self.hitbox.end.y;
-                    self.vel.to.y = 0.;
-                } else if self.vel.to.y < 0.
-                    && self.pos.y + self.hitbox.start.y + COLLISION_THRESHOLD >= other.end.y
-                {
-                    self.pos.y = other.end.y - self.hitbox.start.y;
-                    self.vel.to.y = 0.;
-                }
-            }
-        }
+    /// Add a component to `id`, returning the one it replaces
+    pub fn insert(&mut self, id: EntityId, component: T) -> Option<T> {
+        self.items.insert(id, component)
+    }
+    pub fn remove(&mut self, id: EntityId) -> Option<T> {
+        self.items.remove(&id)
     }
 
-    pub fn tick(&mut self, collide_with: Vec<Region>) {
-        self.vel.limit();
-
-        self.vel.do_falloff();
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        self.items.get(&id)
+    }
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        self.items.get_mut(&id)
+    }
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.items.contains_key(&id)
+    }
 
-        // Apply movement
-        self.pos = self.pos + self.vel.to;
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
 
-        if self.pos.z < 0. {
-            self.pos.z = 0.;
-            self.vel.to.z = 0.;
-            self.in_air = false;
-        }
+    /// Entities having this component, in ascending order
+    pub fn ids(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.items.keys().copied()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.items.iter().map(|(id, c)| (*id, c))
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut T)> {
+        self.items.iter_mut().map(|(id, c)| (*id, c))
+    }
 
-        self.collide(collide_with);
+    /// Query the entities having both this component and one in `other`
+    pub fn with<'a, U>(&'a self, other: &'a Storage<U>) -> impl Iterator<Item = (EntityId, &'a T, &'a U)> {
+        self.iter().filter_map(move |(id, c)| Some((id, c, other.get(id)?)))
+    }
+    /// Same as `with()`, but the components of this storage can be changed
+    pub fn with_mut<'a, U>(&'a mut self, other: &'a Storage<U>) -> impl Iterator<Item = (EntityId, &'a mut T, &'a U)> {
+        self.iter_mut().filter_map(move |(id, c)| Some((id, c, other.get(id)?)))
     }
 }
@@ -14,7 +14,7 @@ pub struct Level {
 impl Level {
     pub fn new(boundary: Region) -> Self {
         Self {
-            boundary: boundary,
+            boundary,
 
             #[cfg(not(feature = "bare"))]
             bg_texture: None,
@@ -1,63 +1,248 @@
 pub mod base;
 pub mod entity;
+pub mod components;
+pub mod systems;
 pub mod player;
+pub mod ai;
+pub mod navigation;
+#[cfg(feature = "rhai")]
+pub mod scripting;
 pub mod level;
+pub mod collision;
+pub mod tilemap;
 
 pub use base::*;
-pub use entity::Entity;
-pub use player::Player;
+pub use entity::{EntityId, Storage};
+pub use components::{Components, Position, Collider, Sprite, Properties};
+pub use systems::System;
+pub use player::{PlayerControlled, player_input};
+pub use ai::{Ai, Behaviour};
+pub use navigation::NavGrid;
+#[cfg(feature = "rhai")]
+pub use scripting::Script;
 pub use level::Level;
+pub use collision::{Contact, CollisionEvent, CollisionPhase};
+pub use tilemap::{Tilemap, TileIndex};
 
 #[cfg(feature = "alloc")]
-use alloc::vec::Vec;
+use alloc::{collections::BTreeSet, vec::Vec};
+#[cfg(not(feature = "alloc"))]
+use std::collections::BTreeSet;
+
+#[cfg(not(feature = "bare"))]
+use {image::RgbaImage, std::{collections::BTreeMap, sync::Arc}};
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
-    pub player: Player,
-    pub entities: Vec<Entity>,
+    /// Every living entity, their data is in `components`
+    pub entities: BTreeSet<EntityId>,
+    pub components: Components,
+    /// Tile layers, drawn in order below the entities
+    pub tilemaps: Vec<Tilemap>,
     pub boundary: Region,
     /// Screen scroll amount relative to world origin (x, y)
     pub scroll: (f64, f64),
     /// Start scrolling screen when player hits given distance to canvas border (in percent)
     pub scroll_threshold: f64,
+    /// Run in order by `tick()`
+    #[cfg_attr(feature = "serde", serde(skip, default = "systems::default_systems"))]
+    pub systems: Vec<System>,
+    /// Compiled script assets, run by the `Script` components referring to them
+    #[cfg(feature = "rhai")]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub scripts: scripting::Scripts,
+    /// Imported sound assets, played by `audio::play_triggered()`
+    #[cfg(not(feature = "bare"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub sounds: crate::audio::Sounds,
+    /// Imported fonts by asset path, for `Engine::draw_text()`
+    #[cfg(not(feature = "bare"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub fonts: BTreeMap<String, Arc<crate::engine::Font>>,
+    /// Widgets drawn over the world by `Engine::render_ui()`
+    #[cfg(not(feature = "bare"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub ui: crate::ui::Ui,
+    /// Collisions that began, lasted or ended during the last `tick()`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub events: Vec<CollisionEvent>,
+    /// Players that jumped during the last `tick()`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub jumps: Vec<EntityId>,
+    /// Entities standing on top of another one after the last `tick()`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub resting: Vec<EntityId>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    touching: Vec<collision::Touch>,
+    #[cfg_attr(feature = "serde", serde(skip, default = "navigation::next_revision"))]
+    obstacle_revision: usize,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
+    /// World with a single player at the origin
     pub fn new() -> World {
-        World {
-            player: Player::new(),
-            entities: Vec::new(),
+        let mut world = World {
+            entities: BTreeSet::new(),
+            components: Components::default(),
+            tilemaps: Vec::new(),
             boundary: Region::default_boundary(),
             scroll: (0.0, 0.0),
             scroll_threshold: 0.85,
+            systems: systems::default_systems(),
+            #[cfg(feature = "rhai")]
+            scripts: scripting::Scripts::new(),
+            #[cfg(not(feature = "bare"))]
+            sounds: crate::audio::Sounds::default(),
+            #[cfg(not(feature = "bare"))]
+            fonts: BTreeMap::new(),
+            #[cfg(not(feature = "bare"))]
+            ui: crate::ui::Ui::new(),
+            events: Vec::new(),
+            jumps: Vec::new(),
+            resting: Vec::new(),
+            touching: Vec::new(),
+            obstacle_revision: navigation::next_revision(),
+        };
+        world.spawn_player(Coord::origin());
+        world
+    }
+
+    /// New entity without any components
+    pub fn spawn(&mut self) -> EntityId {
+        let id = entity::next_id();
+        self.entities.insert(id);
+        id
+    }
+
+    /// New entity that moves and collides
+    pub fn spawn_body(&mut self, pos: Position) -> EntityId {
+        let id = self.spawn();
+        self.components.position.insert(id, pos);
+        self.components.velocity.insert(id, Velocity::new());
+        self.components.collider.insert(id, Collider::new());
+        self.obstacles_changed();
+        id
+    }
+
+    /// New player, controlled by the first key mapping no other player uses
+    pub fn spawn_player(&mut self, pos: Position) -> EntityId {
+        let players = &self.components.player;
+        let input = (0..).find(|i| players.iter().all(|(_, p)| p.input != *i)).unwrap_or(0);
+
+        let id = self.spawn_body(pos);
+        self.components.player.insert(id, PlayerControlled { input });
+        id
+    }
+
+    pub fn despawn(&mut self, id: EntityId) {
+        if navigation::is_obstacle(&self.components, id) {
+            self.obstacles_changed();
         }
+        self.entities.remove(&id);
+        self.components.remove(id);
     }
-    pub fn scroll(&mut self, canvas_center: (f64, f64), canvas_size: (f64, f64)) {
-        if self.player.entity.pos.x - self.scroll.0 + canvas_center.0 > canvas_size.0 * self.scroll_threshold {
-            self.scroll.0 += self.player.entity.pos.x - self.scroll.0 + canvas_center.0 - canvas_size.0 * self.scroll_threshold;
-        } else if self.player.entity.pos.x - self.scroll.0 + canvas_center.0 < canvas_size.0 * (1.0 - self.scroll_threshold) {
-            self.scroll.0 += self.player.entity.pos.x - self.scroll.0 + canvas_center.0 - canvas_size.0 * (1.0 - self.scroll_threshold);
+
+    /// The first player controlled entity
+    pub fn player(&self) -> Option<EntityId> {
+        self.components.player.ids().next()
+    }
+
+    /// Region of an entity with a `Collider` relative to the world
+    pub fn region(&self, id: EntityId) -> Option<Region> {
+        let pos = self.components.position.get(id)?;
+        Some(self.components.collider.get(id)?.absolute(*pos))
+    }
+
+    /// Set the sprite of `id`, sizing its hitbox to the texture
+    #[cfg(not(feature = "bare"))]
+    pub fn set_texture(&mut self, id: EntityId, texture: Option<RgbaImage>, path: Option<String>) {
+        if let (Some(bitmap), Some(collider)) = (&texture, self.components.collider.get_mut(id)) {
+            let (w, h) = (bitmap.width(), bitmap.height());
+            collider.hitbox.start.x = -(w as f64 / 2.);
+            collider.hitbox.start.y = -(h as f64 / 2.);
+            collider.hitbox.end.x = w as f64 / 2.;
+            collider.hitbox.end.y = h as f64 / 2.;
+            if navigation::is_obstacle(&self.components, id) {
+                self.obstacles_changed();
+            }
         }
+        self.components.sprite.insert(id, Sprite { texture, path });
+    }
 
-        if self.player.entity.pos.y - self.scroll.1 + canvas_center.1 > canvas_size.1 * self.scroll_threshold {
-            self.scroll.1 += self.player.entity.pos.y - self.scroll.1 + canvas_center.1 - canvas_size.1 * self.scroll_threshold;
-        } else if self.player.entity.pos.y - self.scroll.1 + canvas_center.1 < canvas_size.1 * (1.0 - self.scroll_threshold) {
-            self.scroll.1 += self.player.entity.pos.y - self.scroll.1 + canvas_center.1 - canvas_size.1 * (1.0 - self.scroll_threshold);
+    /// Scroll the screen so every player stays within the threshold of the canvas border
+    ///
+    /// If they are too far apart for that, the screen is centered between them.
+    pub fn scroll(&mut self, canvas_center: (f64, f64), canvas_size: (f64, f64)) {
+        let c = &self.components;
+        let mut positions = c.player.ids().filter_map(|id| c.position.get(id));
+        let first = match positions.next() {
+            Some(pos) => *pos,
+            None => return,
+        };
+        let (mut min, mut max) = (first, first);
+        for pos in positions {
+            min.x = min.x.min(pos.x);
+            min.y = min.y.min(pos.y);
+            max.x = max.x.max(pos.x);
+            max.y = max.y.max(pos.y);
         }
+
+        let threshold = self.scroll_threshold;
+        self.scroll.0 = follow(self.scroll.0, (min.x, max.x), canvas_center.0, canvas_size.0, threshold);
+        self.scroll.1 = follow(self.scroll.1, (min.y, max.y), canvas_center.1, canvas_size.1, threshold);
+    }
+
+    /// Changes whenever the boundary, tilemaps or obstacles (see `NavGrid`) may have changed,
+    /// and differs between worlds
+    pub fn obstacle_revision(&self) -> usize {
+        self.obstacle_revision
     }
-    pub fn tick(&mut self) {
-        // FIXME: do all this without cloning and copying, thus more efficiently
 
-        let collision_partners: Vec<Region> = self.entities.iter().map(|e| { e.absolute_pos() }).collect();
+    /// Give the world a new `obstacle_revision()`
+    ///
+    /// Spawning, moving and despawning entities does this already, game code changing
+    /// `boundary`, `tilemaps` or colliders directly calls it afterwards.
+    pub fn obstacles_changed(&mut self) {
+        self.obstacle_revision = navigation::next_revision();
+    }
 
-        self.player.tick(collision_partners.clone());
+    /// Whether `id` stands on the ground or on top of another entity, so it can jump
+    pub fn on_ground(&self, id: EntityId) -> bool {
+        self.resting.contains(&id) || self.components.position.get(id).is_none_or(|pos| pos.z <= 0.)
+    }
 
-        for entity in self.entities.iter_mut() {
-            let self_region = collision_partners.iter().position(|r| { *r == entity.absolute_pos() }).unwrap();
-            let mut regions_without_self = collision_partners.clone();
-            assert!(regions_without_self.remove(self_region) == entity.absolute_pos());
+    /// All player controlled entities
+    pub fn players(&self) -> impl Iterator<Item = EntityId> + '_ {
+        self.components.player.ids()
+    }
 
-            entity.tick(regions_without_self);
+    /// Run every system once
+    pub fn tick(&mut self) {
+        self.jumps.clear();
+        let systems = self.systems.clone();
+        for system in systems.iter() {
+            system(self);
         }
     }
-}
\ No newline at end of file
+}
+
+/// Scroll along one axis so the span `(min, max)` of the players stays within the threshold
+fn follow(scroll: f64, (min, max): (f64, f64), center: f64, size: f64, threshold: f64) -> f64 {
+    let (low, high) = (size * (1.0 - threshold), size * threshold);
+    if max - min > high - low {
+        (min + max) / 2. + center - size / 2.
+    } else if max - scroll + center > high {
+        max + center - high
+    } else if min - scroll + center < low {
+        min + center - low
+    } else {
+        scroll
+    }
+}
@@ -0,0 +1,206 @@
+//! Finding paths around obstacles with A* on a grid
+//!
+//! A `NavGrid` is a snapshot of the world: rebuild it when obstacles move, which
+//! `World::obstacle_revision()` tells.
+
+use super::*;
+
+#[cfg(feature = "alloc")]
+use alloc::{collections::BinaryHeap, vec, vec::Vec};
+#[cfg(not(feature = "alloc"))]
+use std::collections::BinaryHeap;
+
+use core::{cmp::Reverse, sync::atomic::{AtomicUsize, Ordering}};
+
+/// Cell size used when game code has no better idea
+pub const DEFAULT_CELL_SIZE: f64 = 16.;
+
+/// Path costs, in tenths of a cell
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Which cells of the world an entity of a given size can stand on
+///
+/// Obstacles are the boundary, solid tiles and every solid entity that stays put: players,
+/// `Ai` entities and pushable entities are expected to move out of the way.
+#[derive(Clone, Debug)]
+pub struct NavGrid {
+    /// Top left corner of the first cell
+    pub origin: Coord,
+    pub cell_size: f64,
+    pub columns: usize,
+    pub rows: usize,
+    /// Hitbox the grid was built for
+    pub hitbox: Region,
+    blocked: Vec<bool>,
+}
+
+static NEXT_REVISION: AtomicUsize = AtomicUsize::new(0);
+
+/// Obstacle revision no `World` had before, see `World::obstacle_revision()`
+pub(in crate::world) fn next_revision() -> usize {
+    NEXT_REVISION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Whether `id` is a solid entity that stays put, see `NavGrid`
+pub(in crate::world) fn is_obstacle(c: &Components, id: EntityId) -> bool {
+    c.collider.get(id).is_some_and(|collider| collider.solid && !collider.pushable)
+        && !c.player.contains(id)
+        && !c.ai.contains(id)
+}
+
+impl NavGrid {
+    /// Grid covering `world.boundary`, for an entity with `hitbox`
+    pub fn build(world: &World, cell_size: f64, hitbox: Region) -> NavGrid {
+        let boundary = world.boundary;
+        let columns = cells_along(boundary.end.x - boundary.start.x, cell_size);
+        let rows = cells_along(boundary.end.y - boundary.start.y, cell_size);
+        let mut grid = NavGrid {
+            origin: Coord { x: boundary.start.x, y: boundary.start.y, z: 0. },
+            cell_size,
+            columns,
+            rows,
+            hitbox,
+            blocked: vec![false; columns * rows],
+        };
+
+        let c = &world.components;
+        let obstacles: Vec<Region> = c
+            .collider
+            .with(&c.position)
+            .filter(|(id, _, _)| is_obstacle(c, *id))
+            .map(|(_, collider, pos)| collider.absolute(*pos))
+            .collect();
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let center = grid.cell_center(column, row);
+                let body = Region { start: center + hitbox.start, end: center + hitbox.end };
+                let outside = body.start.x < boundary.start.x
+                    || body.start.y < boundary.start.y
+                    || body.end.x > boundary.end.x
+                    || body.end.y > boundary.end.y;
+                grid.blocked[row * columns + column] = outside
+                    || obstacles.iter().any(|o| o.overlaps(body))
+                    || world.tilemaps.iter().any(|t| hits_solid_tile(t, body));
+            }
+        }
+        grid
+    }
+
+    pub fn is_blocked(&self, column: usize, row: usize) -> bool {
+        column >= self.columns || row >= self.rows || self.blocked[row * self.columns + column]
+    }
+
+    /// Cell (column, row) containing `pos`, if it is on the grid
+    pub fn cell_of(&self, pos: Coord) -> Option<(usize, usize)> {
+        let x = (pos.x - self.origin.x) / self.cell_size;
+        let y = (pos.y - self.origin.y) / self.cell_size;
+        if x < 0. || y < 0. || x >= self.columns as f64 || y >= self.rows as f64 {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    pub fn cell_center(&self, column: usize, row: usize) -> Coord {
+        Coord {
+            x: self.origin.x + (column as f64 + 0.5) * self.cell_size,
+            y: self.origin.y + (row as f64 + 0.5) * self.cell_size,
+            z: 0.,
+        }
+    }
+
+    /// Shortest path from `from` to `to`, as cell centers to walk through ending at `to`
+    ///
+    /// Diagonal steps never cut the corner of a blocked cell. Returns `None` if either end is
+    /// blocked or they are not connected.
+    pub fn find_path(&self, from: Coord, to: Coord) -> Option<Vec<Coord>> {
+        let start = self.cell_of(from)?;
+        let goal = self.cell_of(to)?;
+        if self.is_blocked(start.0, start.1) || self.is_blocked(goal.0, goal.1) {
+            return None;
+        }
+        let index = |(column, row): (usize, usize)| row * self.columns + column;
+
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut came_from = vec![usize::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[index(start)] = 0;
+        open.push(Reverse((heuristic(start, goal), index(start))));
+
+        while let Some(Reverse((_, current))) = open.pop() {
+            let cell = (current % self.columns, current / self.columns);
+            if cell == goal {
+                return Some(self.walk_back(&came_from, current, to));
+            }
+
+            for (dx, dy) in NEIGHBOURS.iter() {
+                let next = match self.step(cell, *dx, *dy) {
+                    Some(next) => next,
+                    None => continue,
+                };
+                let step_cost = if *dx != 0 && *dy != 0 { DIAGONAL_COST } else { STRAIGHT_COST };
+                let new_cost = cost[current] + step_cost;
+                if new_cost < cost[index(next)] {
+                    cost[index(next)] = new_cost;
+                    came_from[index(next)] = current;
+                    open.push(Reverse((new_cost + heuristic(next, goal), index(next))));
+                }
+            }
+        }
+        None
+    }
+
+    /// Neighbour of `cell` in direction `(dx, dy)`, if it can be walked to
+    fn step(&self, cell: (usize, usize), dx: i64, dy: i64) -> Option<(usize, usize)> {
+        let column = cell.0 as i64 + dx;
+        let row = cell.1 as i64 + dy;
+        if column < 0 || row < 0 || self.is_blocked(column as usize, row as usize) {
+            return None;
+        }
+        if dx != 0 && dy != 0
+            && (self.is_blocked(column as usize, cell.1) || self.is_blocked(cell.0, row as usize))
+        {
+            return None;
+        }
+        Some((column as usize, row as usize))
+    }
+
+    fn walk_back(&self, came_from: &[usize], goal: usize, to: Coord) -> Vec<Coord> {
+        let mut path = vec![to];
+        let mut current = came_from[goal];
+        // The start cell is left out, the entity is already there
+        while current != usize::MAX && came_from[current] != usize::MAX {
+            path.push(self.cell_center(current % self.columns, current / self.columns));
+            current = came_from[current];
+        }
+        path.reverse();
+        path
+    }
+}
+
+const NEIGHBOURS: [(i64, i64); 8] = [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)];
+
+/// Octile distance, exact on an empty grid
+fn heuristic(a: (usize, usize), b: (usize, usize)) -> u32 {
+    let dx = (a.0 as i64 - b.0 as i64).unsigned_abs() as u32;
+    let dy = (a.1 as i64 - b.1 as i64).unsigned_abs() as u32;
+    STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+}
+
+fn cells_along(length: f64, cell_size: f64) -> usize {
+    if length <= 0. || cell_size <= 0. {
+        return 0;
+    }
+    let cells = length / cell_size;
+    if cells > cells as usize as f64 { cells as usize + 1 } else { cells as usize }
+}
+
+fn hits_solid_tile(tilemap: &Tilemap, body: Region) -> bool {
+    let (columns, mut rows) = tilemap.cells_in(body);
+    rows.any(|row| {
+        columns.clone().any(|column| {
+            tilemap.is_solid(column, row) && tilemap.cell_region(column, row).overlaps(body)
+        })
+    })
+}
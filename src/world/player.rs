@@ -1,25 +1,37 @@
 use super::*;
-use crate::engine::{MOVEMENT_KEYS};
+use crate::engine::MOVEMENT_KEYS;
 
-#[derive(Clone)]
-pub struct Player {
-    pub entity: Entity,
+/// Marks an entity as moved by keyboard input
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerControlled {
+    /// Index of the key mapping in `MOVEMENT_KEYS`, players sharing it move together
+    pub input: usize,
 }
 
-impl Player {
-    pub(in crate::world) fn new() -> Player {
-        Player {
-            entity: Entity::new(),
+/// Accelerate player controlled entities according to `MOVEMENT_KEYS`
+pub fn player_input(world: &mut World) {
+    let held = unsafe { MOVEMENT_KEYS };
+    let grounded: Vec<EntityId> = world.players().filter(|id| world.on_ground(*id)).collect();
+    let c = &mut world.components;
+    let jumps = &mut world.jumps;
+    for (id, player) in c.player.iter() {
+        let keys = match held.get(player.input) {
+            Some(keys) => *keys,
+            None => continue,
+        };
+        let on_ground = grounded.contains(&id);
+        let vel = match c.velocity.get_mut(id) {
+            Some(vel) => vel,
+            None => continue,
+        };
+        if keys.up    { vel.to.y -= vel.falloff.y * 2.; }
+        if keys.down  { vel.to.y += vel.falloff.y * 2.; }
+        if keys.left  { vel.to.x -= vel.falloff.x * 2.; }
+        if keys.right { vel.to.x += vel.falloff.x * 2.; }
+        if keys.jump && on_ground {
+            vel.to.z += vel.max.z;
+            jumps.push(id);
         }
     }
-    pub(in crate::world) fn tick(&mut self, collide_with: Vec<Region>) {
-        unsafe {
-            if MOVEMENT_KEYS.up    { self.entity.vel.to.y -= self.entity.vel.falloff.y * 2.; }
-            if MOVEMENT_KEYS.down  { self.entity.vel.to.y += self.entity.vel.falloff.y * 2.; }
-            if MOVEMENT_KEYS.left  { self.entity.vel.to.x -= self.entity.vel.falloff.x * 2.; }
-            if MOVEMENT_KEYS.right { self.entity.vel.to.x += self.entity.vel.falloff.x * 2.; }
-            if MOVEMENT_KEYS.jump && !self.entity.in_air { self.entity.vel.to.z += self.entity.vel.max.z; self.entity.in_air = true; }
-        }
-        self.entity.tick(collide_with);
-    }
 }
@@ -0,0 +1,225 @@
+//! Entity behaviour written in [Rhai](https://rhai.rs) scripts
+//!
+//! A script asset defines `fn tick(world, id)`, called every tick for each entity with a
+//! `Script` component pointing to it. Through `world` it can read and change positions and
+//! velocities, look at collisions and spawn entities, but nothing outside the `World`:
+//!
+//! - `position(id)`, `set_position(id, pos)`, `velocity(id)`, `set_velocity(id, vel)`
+//! - `collisions(id)`: ids of the entities touching `id` since the last tick
+//! - `players()`, `property(id, name)`
+//! - `spawn_body(pos)`: id of a new entity at `pos`, `coord(x, y, z)` makes positions
+//!
+//! ```rhai
+//! fn tick(world, id) {
+//!     if world.collisions(id).len() > 0 {
+//!         let vel = world.velocity(id);
+//!         vel.z = 1.5;
+//!         world.set_velocity(id, vel);
+//!     }
+//! }
+//! ```
+
+use super::*;
+
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Scope, AST, FLOAT, INT};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt, mem,
+    sync::{Arc, Mutex},
+};
+
+/// Stops scripts stuck in a loop
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// Compiled scripts by asset path
+pub type Scripts = BTreeMap<String, Arc<AST>>;
+
+/// Runs the `tick` function of a script on its entity
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Script {
+    /// Asset path of the script in `World::scripts`
+    pub path: String,
+    /// Why the last run failed, the script still runs on the next tick
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub error: Option<String>,
+}
+
+impl Script {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into(), error: None }
+    }
+}
+
+#[derive(Debug)]
+pub struct ScriptError {
+    reason: String,
+}
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Invalid script: {}", self.reason)
+    }
+}
+impl Error for ScriptError {}
+
+/// The parts of `World` a script can reach, lent to it for the duration of `scripts()`
+struct Lent {
+    entities: BTreeSet<EntityId>,
+    components: Components,
+    events: Vec<CollisionEvent>,
+    /// A script spawned or moved an obstacle, see `World::obstacles_changed()`
+    obstacles_changed: bool,
+}
+
+/// Handle to the world passed to scripts as `world`
+#[derive(Clone)]
+struct ScriptWorld(Arc<Mutex<Lent>>);
+
+impl ScriptWorld {
+    fn with<T>(&mut self, f: impl FnOnce(&mut Lent) -> T) -> T {
+        let mut lent = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut lent)
+    }
+}
+
+/// Entity ids are plain integers in scripts
+fn entity(id: INT) -> Option<EntityId> {
+    if id < 0 { None } else { Some(id as EntityId) }
+}
+
+fn ids(ids: impl Iterator<Item = EntityId>) -> Array {
+    ids.map(|id| Dynamic::from(id as INT)).collect()
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.on_print(|_| {});
+
+    engine
+        .register_type_with_name::<Coord>("Coord")
+        .register_fn("coord", |x: FLOAT, y: FLOAT, z: FLOAT| Coord { x, y, z })
+        .register_get_set("x", |c: &mut Coord| c.x, |c: &mut Coord, v: FLOAT| c.x = v)
+        .register_get_set("y", |c: &mut Coord| c.y, |c: &mut Coord, v: FLOAT| c.y = v)
+        .register_get_set("z", |c: &mut Coord| c.z, |c: &mut Coord, v: FLOAT| c.z = v)
+        .register_fn("to_string", |c: &mut Coord| format!("({}, {}, {})", c.x, c.y, c.z));
+
+    engine
+        .register_type_with_name::<ScriptWorld>("World")
+        .register_fn("position", |w: &mut ScriptWorld, id: INT| {
+            w.with(|l| entity(id).and_then(|id| l.components.position.get(id)).map_or(Dynamic::UNIT, |p| Dynamic::from(*p)))
+        })
+        .register_fn("set_position", |w: &mut ScriptWorld, id: INT, pos: Coord| {
+            w.with(|l| {
+                if let Some(id) = entity(id) {
+                    if let Some(p) = l.components.position.get_mut(id) {
+                        *p = pos;
+                        l.obstacles_changed |= navigation::is_obstacle(&l.components, id);
+                    }
+                }
+            })
+        })
+        .register_fn("velocity", |w: &mut ScriptWorld, id: INT| {
+            w.with(|l| entity(id).and_then(|id| l.components.velocity.get(id)).map_or(Dynamic::UNIT, |v| Dynamic::from(v.to)))
+        })
+        .register_fn("set_velocity", |w: &mut ScriptWorld, id: INT, to: Coord| {
+            w.with(|l| {
+                if let Some(v) = entity(id).and_then(|id| l.components.velocity.get_mut(id)) {
+                    v.to = to;
+                }
+            })
+        })
+        .register_fn("collisions", |w: &mut ScriptWorld, id: INT| {
+            w.with(|l| {
+                let touching = l.events.iter().filter(|e| e.phase != CollisionPhase::End);
+                ids(touching.filter_map(|e| match entity(id) {
+                    Some(id) if e.a == id => Some(e.b),
+                    Some(id) if e.b == id => Some(e.a),
+                    _ => None,
+                }))
+            })
+        })
+        .register_fn("players", |w: &mut ScriptWorld| w.with(|l| ids(l.components.player.ids())))
+        .register_fn("property", |w: &mut ScriptWorld, id: INT, name: &str| {
+            w.with(|l| {
+                entity(id)
+                    .and_then(|id| l.components.properties.get(id))
+                    .and_then(|p| p.get(name))
+                    .map_or(Dynamic::UNIT, |v| Dynamic::from(v.clone()))
+            })
+        })
+        .register_fn("spawn_body", |w: &mut ScriptWorld, pos: Coord| {
+            w.with(|l| {
+                let id = entity::next_id();
+                l.entities.insert(id);
+                l.components.position.insert(id, pos);
+                l.components.velocity.insert(id, Velocity::new());
+                l.components.collider.insert(id, Collider::new());
+                l.obstacles_changed = true;
+                id as INT
+            })
+        });
+
+    engine
+}
+
+thread_local! {
+    static ENGINE: Engine = engine();
+}
+
+/// Parse a script asset
+pub fn compile(source: &str) -> Result<AST, Box<dyn Error>> {
+    ENGINE.with(|engine| engine.compile(source)).map_err(|e| {
+        Box::new(ScriptError { reason: e.to_string() }) as Box<dyn Error>
+    })
+}
+
+/// Call the `tick` function of every entity's script
+pub fn scripts(world: &mut World) {
+    if world.components.script.is_empty() {
+        return;
+    }
+    let runs: Vec<(EntityId, String)> = world.components.script.iter().map(|(id, s)| (id, s.path.clone())).collect();
+
+    let handle = ScriptWorld(Arc::new(Mutex::new(Lent {
+        entities: mem::take(&mut world.entities),
+        components: mem::take(&mut world.components),
+        events: world.events.clone(),
+        obstacles_changed: false,
+    })));
+
+    let mut errors = Vec::new();
+    ENGINE.with(|engine| {
+        for (id, path) in runs {
+            let result = match world.scripts.get(&path) {
+                Some(ast) => engine
+                    .call_fn_with_options::<Dynamic>(
+                        CallFnOptions::new().eval_ast(false),
+                        &mut Scope::new(),
+                        ast,
+                        "tick",
+                        (handle.clone(), id as INT),
+                    )
+                    .map(|_| ())
+                    .map_err(|e| e.to_string()),
+                None => Err(format!("{} is not loaded", path)),
+            };
+            errors.push((id, result.err()));
+        }
+    });
+
+    let mut handle = handle;
+    handle.with(|lent| {
+        world.entities = mem::take(&mut lent.entities);
+        world.components = mem::take(&mut lent.components);
+        if lent.obstacles_changed {
+            world.obstacles_changed();
+        }
+    });
+    for (id, error) in errors {
+        if let Some(script) = world.components.script.get_mut(id) {
+            script.error = error;
+        }
+    }
+}
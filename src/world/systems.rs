@@ -0,0 +1,51 @@
+use super::*;
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+
+/// A step of `World::tick()`, working on the components it cares about
+pub type System = fn(&mut World);
+
+/// Systems of a new `World`, in the order they run
+pub fn default_systems() -> Vec<System> {
+    let mut systems: Vec<System> = vec![player_input, ai::ai];
+    #[cfg(feature = "rhai")]
+    systems.push(scripting::scripts);
+    systems.push(movement);
+    systems.push(collision::collision);
+    systems
+}
+
+/// Apply velocities to positions, keeping entities above the ground
+///
+/// Collisions are resolved afterwards, against every other entity at once.
+pub fn movement(world: &mut World) {
+    let c = &mut world.components;
+    let mut moved = Vec::new();
+    for (id, vel) in c.velocity.iter_mut() {
+        let pos = match c.position.get_mut(id) {
+            Some(pos) => pos,
+            None => continue,
+        };
+
+        vel.limit();
+
+        vel.do_falloff();
+
+        // Apply movement
+        let before = *pos;
+        *pos = *pos + vel.to;
+
+        if pos.z < 0. {
+            pos.z = 0.;
+            vel.to.z = 0.;
+        }
+        if *pos != before {
+            moved.push(id);
+        }
+    }
+
+    if moved.into_iter().any(|id| navigation::is_obstacle(&world.components, id)) {
+        world.obstacles_changed();
+    }
+}
@@ -0,0 +1,141 @@
+use super::*;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "bare"))]
+use image::RgbaImage;
+
+/// Index of a tile in the tileset, counting left to right, top to bottom
+pub type TileIndex = u32;
+
+/// Grid of tiles drawn from a single tileset texture
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tilemap {
+    /// Top left corner of the map relative to world origin
+    pub origin: Coord,
+    /// Size of a single tile in pixels (width, height)
+    pub tile_size: (u32, u32),
+    /// Number of tiles in a row
+    pub columns: usize,
+    /// Number of tiles in a column
+    pub rows: usize,
+    /// Tiles row by row, `None` marks an empty cell
+    pub tiles: Vec<Option<TileIndex>>,
+    /// Solidity of each tileset index, missing entries are not solid
+    pub solid: Vec<bool>,
+    /// Height of solid tiles, entities above it pass over them
+    pub height: f64,
+
+    #[cfg(not(feature = "bare"))]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub tileset: Option<RgbaImage>,
+    /// Asset path of `tileset`, saved instead of the pixel data
+    #[cfg(not(feature = "bare"))]
+    pub tileset_path: Option<String>,
+    /// Asset path of the level this layer belongs to, so it can be replaced when reloading
+    #[cfg(not(feature = "bare"))]
+    pub source: Option<String>,
+}
+
+impl Tilemap {
+    /// Empty map of the given size
+    pub fn new(columns: usize, rows: usize, tile_size: (u32, u32)) -> Self {
+        let mut tiles = Vec::new();
+        tiles.resize(columns * rows, None);
+        Self {
+            origin: Coord::origin(),
+            tile_size,
+            columns,
+            rows,
+            tiles,
+            solid: Vec::new(),
+            height: 1000.,
+
+            #[cfg(not(feature = "bare"))]
+            tileset: None,
+            #[cfg(not(feature = "bare"))]
+            tileset_path: None,
+            #[cfg(not(feature = "bare"))]
+            source: None,
+        }
+    }
+
+    pub fn get(&self, column: usize, row: usize) -> Option<TileIndex> {
+        if column >= self.columns || row >= self.rows {
+            return None;
+        }
+        self.tiles[row * self.columns + column]
+    }
+
+    pub fn set(&mut self, column: usize, row: usize, tile: Option<TileIndex>) {
+        if column < self.columns && row < self.rows {
+            self.tiles[row * self.columns + column] = tile;
+        }
+    }
+
+    pub fn is_solid(&self, column: usize, row: usize) -> bool {
+        match self.get(column, row) {
+            Some(tile) => self.solid.get(tile as usize).copied().unwrap_or(false),
+            None => false,
+        }
+    }
+
+    /// Region covered by a single cell, relative to world origin
+    pub fn cell_region(&self, column: usize, row: usize) -> Region {
+        let (w, h) = (self.tile_size.0 as f64, self.tile_size.1 as f64);
+        let x = self.origin.x + column as f64 * w;
+        let y = self.origin.y + row as f64 * h;
+        Region {
+            start: Coord { x, y, z: self.origin.z + self.height },
+            end: Coord { x: x + w, y: y + h, z: self.origin.z },
+        }
+    }
+
+    /// Region covered by the whole map, relative to world origin
+    pub fn bounds(&self) -> Region {
+        let mut region = self.cell_region(0, 0);
+        region.end.x = self.origin.x + self.columns as f64 * self.tile_size.0 as f64;
+        region.end.y = self.origin.y + self.rows as f64 * self.tile_size.1 as f64;
+        region
+    }
+
+    /// Range of cells (columns, rows) touched by `region`, clamped to the map
+    pub fn cells_in(&self, region: Region) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+        let (w, h) = (self.tile_size.0 as f64, self.tile_size.1 as f64);
+        let clamp = |v: i64, max: usize| v.max(0).min(max as i64) as usize;
+
+        let c0 = clamp(floor((region.start.x - self.origin.x) / w), self.columns);
+        let c1 = clamp(floor((region.end.x - self.origin.x) / w) + 1, self.columns);
+        let r0 = clamp(floor((region.start.y - self.origin.y) / h), self.rows);
+        let r1 = clamp(floor((region.end.y - self.origin.y) / h) + 1, self.rows);
+        (c0..c1, r0..r1)
+    }
+
+    /// Source rectangle (x, y, w, h) of a tile in the tileset texture
+    #[cfg(not(feature = "bare"))]
+    pub fn tileset_rect(&self, tile: TileIndex) -> Option<(u32, u32, u32, u32)> {
+        let tileset = self.tileset.as_ref()?;
+        let (w, h) = self.tile_size;
+        let per_row = tileset.width() / w.max(1);
+        if per_row == 0 {
+            return None;
+        }
+        let (x, y) = ((tile % per_row) * w, (tile / per_row) * h);
+        if y + h > tileset.height() {
+            return None;
+        }
+        Some((x, y, w, h))
+    }
+
+    #[cfg(not(feature = "bare"))]
+    pub fn set_tileset(&mut self, texture: Option<RgbaImage>) {
+        self.tileset = texture;
+    }
+}
+
+fn floor(v: f64) -> i64 {
+    let t = v as i64;
+    if (t as f64) > v { t - 1 } else { t }
+}
@@ -0,0 +1,91 @@
+//! Behaviours of `Ai` entities in `World::tick`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::world::*;
+
+mod common;
+use common::pos;
+
+/// World with the player at `player` and an enemy at the origin
+fn world(player: Coord, behaviour: Behaviour) -> (World, EntityId) {
+    let mut world = World::new();
+    let id = world.player().unwrap();
+    world.components.position.insert(id, player);
+    let enemy = world.spawn_body(Coord::origin());
+    world.components.collider.get_mut(enemy).unwrap().solid = false;
+    world.components.ai.insert(enemy, Ai::new(behaviour));
+    (world, enemy)
+}
+
+#[test]
+fn chase_only_within_radius() {
+    let (mut world, enemy) = world(Coord { x: 150., y: 0., z: 0. }, Behaviour::Chase { radius: 100. });
+    world.tick();
+    assert_eq!(pos(&world, enemy), Coord::origin());
+
+    let player = world.player().unwrap();
+    world.components.position.insert(player, Coord { x: 0., y: 80., z: 0. });
+    for _ in 0..10 {
+        world.tick();
+    }
+    let enemy = pos(&world, enemy);
+    assert!(enemy.y > 0., "{:?}", enemy.y);
+    assert!(enemy.x.abs() < 1e-9);
+}
+
+#[test]
+fn flee_moves_away() {
+    let (mut world, enemy) = world(Coord { x: 50., y: 0., z: 0. }, Behaviour::Flee { radius: 100. });
+    for _ in 0..10 {
+        world.tick();
+    }
+    assert!(pos(&world, enemy).x < 0.);
+}
+
+#[test]
+fn patrol_cycles_through_waypoints() {
+    let waypoints = vec![Coord { x: 40., y: 0., z: 0. }, Coord::origin()];
+    let (mut world, enemy) = world(Coord { x: 0., y: 500., z: 0. }, Behaviour::Patrol { waypoints, next: 0 });
+
+    let mut furthest: f64 = 0.;
+    let mut turned = false;
+    for _ in 0..200 {
+        world.tick();
+        furthest = furthest.max(pos(&world, enemy).x);
+        if let Some(Ai { behaviour: Behaviour::Patrol { next: 1, .. } }) = world.components.ai.get(enemy) {
+            turned = true;
+        }
+    }
+    assert!(turned);
+    assert!(furthest >= 32.);
+    assert!(pos(&world, enemy).x < furthest);
+}
+
+#[test]
+fn wander_is_repeatable() {
+    let wander = Behaviour::Wander { seed: 7, direction: (0., 0.), ticks: 0 };
+    let (mut a, enemy_a) = world(Coord { x: 0., y: 500., z: 0. }, wander.clone());
+    let (mut b, enemy_b) = world(Coord { x: 0., y: 500., z: 0. }, wander);
+    for _ in 0..400 {
+        a.tick();
+        b.tick();
+    }
+    assert_eq!(pos(&a, enemy_a), pos(&b, enemy_b));
+    assert_ne!(pos(&a, enemy_a), Coord::origin());
+}
+
+#[test]
+fn behaviours_from_level_properties() {
+    let mut properties = Properties::new();
+    assert_eq!(Ai::from_properties(&properties), None);
+
+    properties.insert("ai".to_string(), "patrol".to_string());
+    properties.insert("waypoints".to_string(), "10,20 -5.5,0 bogus".to_string());
+    let waypoints = vec![Coord { x: 10., y: 20., z: 0. }, Coord { x: -5.5, y: 0., z: 0. }];
+    assert_eq!(Ai::from_properties(&properties), Some(Ai::new(Behaviour::Patrol { waypoints, next: 0 })));
+
+    properties.insert("ai".to_string(), "chase".to_string());
+    properties.insert("radius".to_string(), "64".to_string());
+    assert_eq!(Ai::from_properties(&properties), Some(Ai::new(Behaviour::Chase { radius: 64. })));
+}
@@ -0,0 +1,155 @@
+//! Decoding and mixing sounds without an output device.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{
+    assets::{self, AssetSource},
+    audio::{self, mixer::DEFAULT_SAMPLE_RATE, spatial, Listener, Mixer, Sound, Spatial, Trigger, Voice},
+    engine::{MovementKeys, MOVEMENT_KEYS},
+    world::*,
+};
+use futures::executor::block_on;
+use std::{io::Cursor, sync::Arc};
+
+fn wav(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+    let spec = hound::WavSpec { channels, sample_rate, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let mut data = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut data, spec).unwrap();
+    for sample in samples {
+        writer.write_sample(*sample).unwrap();
+    }
+    writer.finalize().unwrap();
+    data.into_inner()
+}
+
+/// Mono sound of `frames` samples at 0.5
+fn tone(sample_rate: u32, frames: usize) -> Arc<Sound> {
+    Arc::new(Sound { sample_rate, channels: 1, samples: vec![0.5; frames] })
+}
+
+fn voice(sound: Arc<Sound>, volume: f32, pan: f32, looping: bool) -> Voice {
+    let mut voice = Voice::new(sound);
+    voice.volume = volume;
+    voice.pan = pan;
+    voice.looping = looping;
+    voice
+}
+
+#[test]
+fn wav_files_decode() {
+    let sound = Sound::decode(&wav(8000, 2, &[16384, -16384, 0, 32767])).unwrap();
+    assert_eq!((sound.sample_rate, sound.channels, sound.frames()), (8000, 2, 2));
+    assert_eq!(sound.samples[..3], [0.5, -0.5, 0.]);
+    assert_eq!(sound.duration(), 2. / 8000.);
+
+    assert!(Sound::decode(b"RIFF but not really").is_err());
+    assert!(Sound::decode(b"OggS but not really").is_err());
+    assert!(Sound::decode(b"no idea").is_err());
+}
+
+#[test]
+fn voices_add_up_with_volume_and_pan() {
+    let mut mixer = Mixer::default();
+    let sound = tone(DEFAULT_SAMPLE_RATE, 4);
+    mixer.play(Voice::new(sound.clone()));
+    mixer.play(voice(sound.clone(), 0.5, -1., false));
+    assert_eq!(mixer.render(1), vec![0.75, 0.5]);
+
+    // Too loud, clipped
+    mixer.play(voice(sound, 4., 0., false));
+    assert_eq!(mixer.render(1), vec![1., 1.]);
+}
+
+#[test]
+fn finished_voices_are_dropped() {
+    let mut mixer = Mixer::default();
+    let once = mixer.play(Voice::new(tone(DEFAULT_SAMPLE_RATE, 3)));
+    let looping = mixer.play(voice(tone(DEFAULT_SAMPLE_RATE, 3), 0.5, 0., true));
+
+    let out = mixer.render(4);
+    assert_eq!(out, vec![0.75, 0.75, 0.75, 0.75, 0.75, 0.75, 0.25, 0.25]);
+    assert!(!mixer.is_playing(once));
+    assert!(mixer.is_playing(looping));
+
+    mixer.voice_mut(looping).unwrap().volume = 1.;
+    assert_eq!(mixer.render(1), vec![0.5, 0.5]);
+    mixer.stop(looping);
+    assert_eq!(mixer.playing(), 0);
+    assert_eq!(mixer.render(1), vec![0., 0.]);
+}
+
+#[test]
+fn sounds_are_resampled() {
+    let mut mixer = Mixer::new(16000);
+    let sound = Arc::new(Sound { sample_rate: 8000, channels: 1, samples: vec![0., 1.] });
+    mixer.play(Voice::new(sound));
+    // Twice as many frames, the ones in between interpolated
+    assert_eq!(mixer.render(4), vec![0., 0., 0.5, 0.5, 1., 1., 1., 1.]);
+    assert_eq!(mixer.playing(), 0);
+}
+
+#[test]
+fn jumps_play_their_sound() {
+    let index = br#"[ { "path": "jump.wav", "type": "Sound", "entity_id": null, "trigger": "Jump" } ]"#;
+    let source = AssetSource::memory(vec![("index.json", index.to_vec()), ("jump.wav", wav(8000, 1, &[1000; 80]))]);
+    let mut world = World::new();
+    let assets = block_on(assets::load_assets(&source)).unwrap();
+    block_on(assets::import_assets(&source, assets, &mut world)).unwrap();
+    assert_eq!(world.sounds.triggers[&Trigger::Jump], "jump.wav");
+
+    let mut mixer = Mixer::default();
+    world.tick();
+    audio::play_triggered(&world, &mut mixer, &Spatial::new());
+    assert_eq!(mixer.playing(), 0);
+
+    unsafe {
+        MOVEMENT_KEYS[0] = MovementKeys { jump: true, ..MovementKeys::new() };
+    }
+    world.tick();
+    unsafe {
+        MOVEMENT_KEYS[0] = MovementKeys::new();
+    }
+    assert_eq!(world.jumps, vec![world.player().unwrap()]);
+    audio::play_triggered(&world, &mut mixer, &Spatial::new());
+    assert_eq!(mixer.playing(), 1);
+}
+
+#[test]
+fn distance_fades_and_pans() {
+    let spatial = Spatial::new();
+    let at = |x: f64, y: f64| Coord { x, y, z: 0. };
+    assert_eq!(spatial.place(at(0., 0.), at(0., 10.)), (1., 0.));
+    assert_eq!(spatial.place(at(0., 0.), at(128., 0.)), (0.5, 0.4));
+    assert_eq!(spatial.place(at(100., 0.), at(-540., 0.)).1, -1.);
+    assert_eq!(spatial.place(at(0., 0.), at(0., 1000.)).0, 0.);
+
+    let flat = Spatial { rolloff: 0., ..Spatial::new() };
+    assert_eq!(flat.place(at(0., 0.), at(0., 500.)).0, 1.);
+}
+
+#[test]
+fn sounds_follow_their_entity() {
+    let mut world = World::new();
+    let enemy = world.spawn_body(Coord { x: 320., y: 0., z: 0. });
+    let mut mixer = Mixer::default();
+    let mut from_enemy = Voice::new(tone(DEFAULT_SAMPLE_RATE, 10));
+    from_enemy.emitter = Some(enemy);
+    let id = mixer.play(from_enemy);
+
+    let spatial = Spatial::new();
+    spatial::spatialize(&world, &mut mixer, &spatial);
+    assert_eq!(mixer.render(1), vec![0., 0.1]);
+
+    // Off screen on the left, the camera has not moved
+    world.components.position.get_mut(enemy).unwrap().x = -320.;
+    spatial::spatialize(&world, &mut mixer, &spatial);
+    assert_eq!(mixer.render(1), vec![0.1, 0.]);
+
+    // Right next to the player
+    let by_player = Spatial { listener: Listener::Player, ..spatial };
+    world.scroll = (1000., 0.);
+    world.components.position.get_mut(world.player().unwrap()).unwrap().x = -300.;
+    spatial::spatialize(&world, &mut mixer, &by_player);
+    let voice = mixer.voice_mut(id).unwrap();
+    assert_eq!((voice.attenuation, voice.pan), (1., -20. / 320.));
+}
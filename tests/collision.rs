@@ -0,0 +1,174 @@
+//! Collision resolution between entities in `World::tick`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::world::*;
+
+mod common;
+use common::pos;
+
+fn moving(world: &mut World, id: EntityId, x: f64, vel_x: f64) -> EntityId {
+    let c = &mut world.components;
+    c.position.insert(id, Coord { x, y: 0., z: 0. });
+    let vel = c.velocity.get_mut(id).unwrap();
+    vel.to.x = vel_x;
+    vel.falloff = Coord::origin();
+    id
+}
+
+fn spawn(world: &mut World, x: f64, vel_x: f64) -> EntityId {
+    let id = world.spawn_body(Coord::origin());
+    moving(world, id, x, vel_x)
+}
+
+fn player(world: &mut World, x: f64, vel_x: f64) -> EntityId {
+    let id = world.player().unwrap();
+    moving(world, id, x, vel_x)
+}
+
+fn vel(world: &World, id: EntityId) -> Coord {
+    world.components.velocity.get(id).unwrap().to
+}
+
+#[test]
+fn moving_entities_meet_halfway() {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.position.get_mut(player).unwrap().y = 500.;
+    let a = spawn(&mut world, -18., 3.);
+    let b = spawn(&mut world, 18., -3.);
+
+    world.tick();
+
+    assert!((pos(&world, a).x + 16.).abs() < 1e-9, "{}", pos(&world, a).x);
+    assert!((pos(&world, b).x - 16.).abs() < 1e-9, "{}", pos(&world, b).x);
+    assert_eq!(vel(&world, a).x, 0.);
+    assert_eq!(vel(&world, b).x, 0.);
+}
+
+#[test]
+fn static_entity_blocks() {
+    let mut world = World::new();
+    let player = player(&mut world, -34., 3.);
+    let wall = spawn(&mut world, 0., 0.);
+
+    world.tick();
+
+    assert!((pos(&world, player).x + 32.).abs() < 1e-9);
+    assert_eq!(pos(&world, wall).x, 0.);
+}
+
+#[test]
+fn player_pushes_heavier_crate_slowly() {
+    let mut world = World::new();
+    let player = player(&mut world, -32., 2.);
+    let crate_ = spawn(&mut world, 0., 0.);
+    let collider = world.components.collider.get_mut(crate_).unwrap();
+    collider.pushable = true;
+    collider.mass = 3.;
+
+    world.tick();
+
+    assert!((pos(&world, crate_).x - 0.5).abs() < 1e-9, "{}", pos(&world, crate_).x);
+    assert!((pos(&world, crate_).x - pos(&world, player).x - 32.).abs() < 1e-9);
+    assert!((vel(&world, player).x - 0.5).abs() < 1e-9);
+    assert!((vel(&world, crate_).x - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn jumping_over_does_not_collide() {
+    let mut world = World::new();
+    let player = player(&mut world, -34., 3.);
+    world.components.position.get_mut(player).unwrap().z = 30.;
+    spawn(&mut world, 0., 0.);
+
+    world.tick();
+
+    assert_eq!(pos(&world, player).x, -31.);
+}
+
+#[test]
+fn falling_onto_an_entity_lands_on_top() {
+    let mut world = World::new();
+    let player = player(&mut world, 10., 0.);
+    world.components.position.get_mut(player).unwrap().z = 18.;
+    world.components.velocity.get_mut(player).unwrap().to.z = -1.;
+    let crate_ = spawn(&mut world, 0., 0.);
+
+    world.tick();
+
+    assert_eq!(pos(&world, player), Coord { x: 10., y: 0., z: 18. });
+    assert_eq!(vel(&world, player).z, 0.);
+    assert_eq!(pos(&world, crate_).x, 0.);
+    assert_eq!(world.resting, vec![player]);
+    assert!(world.on_ground(player));
+}
+
+#[test]
+fn falling_beside_an_entity_does_not_land() {
+    let mut world = World::new();
+    let player = player(&mut world, 34., -3.);
+    world.components.position.get_mut(player).unwrap().z = 6.;
+    world.components.velocity.get_mut(player).unwrap().to.z = -1.;
+    spawn(&mut world, 0., 0.);
+
+    world.tick();
+
+    assert!((pos(&world, player).x - 32.).abs() < 1e-9, "{}", pos(&world, player).x);
+    assert_eq!(pos(&world, player).z, 5.);
+    assert!(world.resting.is_empty());
+    assert!(!world.on_ground(player));
+}
+
+#[test]
+fn touching_reports_begin_stay_end() {
+    let mut world = World::new();
+    let player = player(&mut world, -34., 3.);
+    let wall = spawn(&mut world, 0., 0.);
+
+    world.tick();
+    assert_eq!(world.events.len(), 1);
+    let event = world.events[0];
+    assert_eq!(event.phase, CollisionPhase::Begin);
+    assert_eq!((event.a, event.b), (player.min(wall), player.max(wall)));
+    assert_eq!(event.normal.x, if player < wall { 1. } else { -1. });
+    assert!(!event.trigger);
+
+    world.tick();
+    assert_eq!(world.events[0].phase, CollisionPhase::Stay);
+
+    world.components.velocity.get_mut(player).unwrap().to.x = -3.;
+    world.tick();
+    assert_eq!(world.events.len(), 1);
+    assert_eq!(world.events[0].phase, CollisionPhase::End);
+
+    world.tick();
+    assert!(world.events.is_empty());
+}
+
+#[test]
+fn trigger_does_not_block() {
+    let mut world = World::new();
+    let player = player(&mut world, -34., 3.);
+    let pickup = spawn(&mut world, 0., 0.);
+    world.components.collider.get_mut(pickup).unwrap().solid = false;
+
+    world.tick();
+
+    assert_eq!(pos(&world, player).x, -31.);
+    assert_eq!(world.events.len(), 1);
+    assert!(world.events[0].trigger);
+}
+
+#[test]
+fn entities_without_collider_pass_through() {
+    let mut world = World::new();
+    let player = player(&mut world, -34., 3.);
+    let ghost = spawn(&mut world, 0., 0.);
+    world.components.collider.remove(ghost);
+
+    world.tick();
+
+    assert_eq!(pos(&world, player).x, -31.);
+    assert!(world.events.is_empty());
+}
@@ -0,0 +1,61 @@
+//! Helpers shared by the tests, and the asset directory of the asset tests: a player
+//! texture, a one tile map with a crate, and a script attached to the crate.
+
+#![allow(dead_code)]
+
+use canvasgame_rust::{assets::AssetSource, world::{Coord, EntityId, World}};
+use std::{fs, path::PathBuf};
+
+pub const INDEX: &str = r#"[
+    { "path": "player.png", "type": "Texture", "entity_id": "player" },
+    { "path": "levels/one.json", "type": "TiledMap", "entity_id": null },
+    { "path": "scripts/bounce.rhai", "type": "Script", "entity_id": "crate" }
+]"#;
+
+pub const MAP: &str = r#"{
+    "width": 1, "height": 1, "tilewidth": 16, "tileheight": 16,
+    "orientation": "orthogonal", "infinite": false,
+    "layers": [
+        { "type": "tilelayer", "name": "ground", "width": 1, "height": 1, "data": [1] },
+        { "type": "objectgroup", "name": "objects", "objects": [
+            { "id": 1, "name": "crate", "type": "box", "x": 0, "y": 0, "width": 16, "height": 16,
+              "properties": [ { "name": "script", "type": "string", "value": "scripts/bounce.rhai" } ] }
+        ] }
+    ],
+    "tilesets": [ { "firstgid": 1, "image": "tiles.png", "tilecount": 1, "tilewidth": 16, "tileheight": 16 } ]
+}"#;
+
+pub const SCRIPT: &str = "fn tick(world, id) {}";
+
+/// Every file of the fixture, by path
+pub fn files() -> Vec<(&'static str, Vec<u8>)> {
+    let png = fs::read("assets/player.png").unwrap();
+    vec![
+        ("index.json", INDEX.as_bytes().to_vec()),
+        ("player.png", png.clone()),
+        ("levels/one.json", MAP.as_bytes().to_vec()),
+        ("levels/tiles.png", png),
+        ("scripts/bounce.rhai", SCRIPT.as_bytes().to_vec()),
+    ]
+}
+
+pub fn source() -> AssetSource {
+    AssetSource::memory(files())
+}
+
+/// The fixture written to a fresh temporary directory, unique to `test` and `name`
+pub fn assets_dir(test: &str, name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("canvasgame-{}-{}-{}", test, name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    for (path, data) in files() {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+    root
+}
+
+/// Position of `id`, which has to have one
+pub fn pos(world: &World, id: EntityId) -> Coord {
+    world.components.position.get(id).copied().unwrap()
+}
@@ -0,0 +1,128 @@
+//! Debug overlay drawn through the `Engine` trait.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{
+    engine::{DebugStats, Engine, Font, Pixel},
+    world::*,
+};
+
+/// Engine drawing into a plain pixel buffer
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![(0, 0, 0); width * height] }
+    }
+
+    fn at(&self, x: f64, y: f64) -> Pixel {
+        self.pixels[y as usize * self.width + x as usize]
+    }
+}
+
+impl Engine for Canvas {
+    fn width(&self) -> usize {
+        self.width
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn clear(&mut self) {
+        self.pixels = vec![(0, 0, 0); self.width * self.height];
+    }
+    fn set_at(&mut self, idx: usize, pixel: Pixel) {
+        self.pixels[idx] = pixel;
+    }
+    fn set_at_with_opacity(&mut self, idx: usize, pixel: Pixel, _opacity: f64) {
+        self.pixels[idx] = pixel;
+    }
+}
+
+/// World with only the player, low enough on the canvas to stay clear of the text
+fn world() -> (World, EntityId) {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.position.insert(player, Coord { x: 0., y: 150., z: 0. });
+    (world, player)
+}
+
+#[test]
+fn stats_count_frames_per_second() {
+    let mut stats = DebugStats::new();
+    for frame in 0..20 {
+        stats.frame(frame as f64 * 50.);
+    }
+    assert_eq!(stats.fps, 0.);
+    stats.frame(1000.);
+    assert_eq!(stats.fps, 20.);
+
+    stats.tick(10., 12.5);
+    assert_eq!(stats.tick_time, 2.5);
+}
+
+#[test]
+fn hitboxes_are_outlined() {
+    let (mut world, player) = world();
+    let pickup = world.spawn_body(Coord { x: 100., y: 150., z: 0. });
+    world.components.collider.get_mut(pickup).unwrap().solid = false;
+    let mut canvas = Canvas::new(640, 480);
+
+    canvas.render_debug(&world, &DebugStats::new(), &Font::builtin());
+
+    let center = canvas.center();
+    for (id, color) in [(player, (0, 255, 0)), (pickup, (255, 255, 0))].iter() {
+        let c = &world.components;
+        let region = c.collider.get(*id).unwrap().absolute(*c.position.get(*id).unwrap());
+        assert_eq!(canvas.at(center.0 + region.start.x, center.1 + region.start.y), *color);
+        assert_eq!(canvas.at(center.0 + region.end.x - 1., center.1 + region.end.y - 1.), *color);
+        let middle = (center.0 + (region.start.x + region.end.x) / 2., center.1 + (region.start.y + region.end.y) / 2.);
+        assert_eq!(canvas.at(middle.0, middle.1), (0, 0, 0));
+    }
+}
+
+#[test]
+fn contacts_are_marked_between_entities() {
+    let (mut world, player) = world();
+    world.components.position.insert(player, Coord { x: -32., y: 150., z: 0. });
+    world.spawn_body(Coord { x: 0., y: 150., z: 0. });
+    world.tick();
+    assert!(!world.events.is_empty());
+    let mut canvas = Canvas::new(640, 480);
+
+    canvas.render_debug(&world, &DebugStats::new(), &Font::builtin());
+
+    let center = canvas.center();
+    assert_eq!(canvas.at(center.0 - 16., center.1 + 150.), (255, 0, 0));
+}
+
+#[test]
+fn overlay_follows_scroll() {
+    let (mut world, player) = world();
+    world.scroll = (50., 0.);
+    let mut canvas = Canvas::new(640, 480);
+
+    canvas.render_debug(&world, &DebugStats::new(), &Font::builtin());
+
+    let c = &world.components;
+    let region = c.collider.get(player).unwrap().absolute(*c.position.get(player).unwrap());
+    let center = canvas.center();
+    assert_eq!(canvas.at(center.0 + region.start.x - 50., center.1 + region.start.y), (0, 255, 0));
+}
+
+#[test]
+fn text_is_drawn_on_a_backdrop() {
+    let (world, _) = world();
+    let mut canvas = Canvas::new(640, 480);
+    canvas.fill_rect(0, 0, 640, 480, (0, 0, 255));
+
+    canvas.render_debug(&world, &DebugStats::new(), &Font::builtin());
+
+    let text = canvas.pixels[..40 * 640].iter().filter(|p| **p == (255, 255, 255)).count();
+    let backdrop = canvas.pixels[..40 * 640].iter().filter(|p| **p == (0, 0, 0)).count();
+    assert!(text > 0);
+    assert!(backdrop > text);
+}
@@ -0,0 +1,70 @@
+//! Pausing when the game loses focus.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{
+    engine::{release_keys, DebugStats, MovementKeys, MOVEMENT_KEYS},
+    scene::*,
+    world::*,
+};
+
+fn focus_lost() -> Input {
+    Input { focus_lost: true, ..Input::new((640., 480.)) }
+}
+
+#[test]
+fn held_keys_are_released() {
+    unsafe {
+        MOVEMENT_KEYS[0] = MovementKeys { right: true, jump: true, ..MovementKeys::new() };
+        MOVEMENT_KEYS[1].left = true;
+    }
+    release_keys();
+    let held = unsafe { MOVEMENT_KEYS };
+    assert!(held.iter().all(|keys| *keys == MovementKeys::new()));
+}
+
+#[test]
+fn gameplay_pauses_until_resumed() {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.velocity.get_mut(player).unwrap().to.x = 2.;
+    let mut stack = SceneStack::new(Box::new(Gameplay::new()));
+
+    stack.update(&mut world, &focus_lost());
+    assert_eq!(stack.len(), 2);
+    let x = world.components.position.get(player).unwrap().x;
+
+    // Losing focus again while paused changes nothing
+    stack.update(&mut world, &focus_lost());
+    stack.update(&mut world, &Input::new((640., 480.)));
+    assert_eq!(stack.len(), 2);
+    assert_eq!(world.components.position.get(player).unwrap().x, x);
+
+    let resume = Input { commands: vec![Command::Confirm], ..Input::new((640., 480.)) };
+    stack.update(&mut world, &resume);
+    stack.update(&mut world, &Input::new((640., 480.)));
+    assert_eq!(stack.len(), 1);
+    assert!(world.components.position.get(player).unwrap().x > x);
+}
+
+#[test]
+fn title_ignores_focus() {
+    let mut world = World::new();
+    let mut stack = SceneStack::game();
+    stack.update(&mut world, &focus_lost());
+    assert_eq!(stack.len(), 1);
+}
+
+#[test]
+fn hidden_time_is_not_counted_in_fps() {
+    let mut stats = DebugStats::new();
+    stats.frame(0.);
+    stats.frame(500.);
+
+    // Hidden for ten seconds, then 30 frames in the next second
+    stats.restart();
+    for frame in 0..=30 {
+        stats.frame(10_000. + frame as f64 * 1000. / 30.);
+    }
+    assert!((stats.fps - 30.).abs() < 1e-9);
+}
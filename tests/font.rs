@@ -0,0 +1,141 @@
+//! Drawing text with bitmap fonts.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{
+    assets::{self, AssetSource},
+    engine::{Engine, Font, Pixel},
+    world::*,
+};
+use futures::executor::block_on;
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+/// Engine drawing into a plain pixel buffer
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![(0, 0, 0); width * height] }
+    }
+
+    fn ink(&self) -> usize {
+        self.pixels.iter().filter(|p| **p != (0, 0, 0)).count()
+    }
+}
+
+impl Engine for Canvas {
+    fn width(&self) -> usize {
+        self.width
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn clear(&mut self) {
+        self.pixels = vec![(0, 0, 0); self.width * self.height];
+    }
+    fn set_at(&mut self, idx: usize, pixel: Pixel) {
+        self.pixels[idx] = pixel;
+    }
+    fn set_at_with_opacity(&mut self, idx: usize, pixel: Pixel, _opacity: f64) {
+        self.pixels[idx] = pixel;
+    }
+}
+
+/// Atlas of two 3x3 glyphs side by side, a full square and a dot in the middle
+fn atlas() -> RgbaImage {
+    let mut atlas = RgbaImage::new(6, 3);
+    for (x, y, pixel) in atlas.enumerate_pixels_mut() {
+        if x < 3 || (x, y) == (4, 1) {
+            *pixel = Rgba([255, 255, 255, 255]);
+        }
+    }
+    atlas
+}
+
+#[test]
+fn builtin_font_measures_text() {
+    let font = Font::builtin();
+    assert_eq!(font.measure("Hi"), (12, 8));
+    assert_eq!(font.measure("a\nbcd"), (18, 16));
+    assert_eq!(font.measure(""), (0, 8));
+    // Unknown characters look like `?`
+    assert_eq!(font.glyph('é'), font.glyph('?'));
+}
+
+#[test]
+fn text_is_drawn_in_font_pixels() {
+    let font = Font::builtin();
+    let mut canvas = Canvas::new(40, 20);
+    canvas.draw_text(&font, "I", 0., 0., 1, (255, 255, 255));
+    // Top and bottom bars of three, and the stem between them
+    assert_eq!(canvas.ink(), 3 + 3 + 5);
+    assert_eq!(canvas.pixels[2], (255, 255, 255));
+    assert_eq!(canvas.pixels[0], (0, 0, 0));
+
+    canvas.clear();
+    canvas.draw_text(&font, "I", 0., 0., 2, (255, 0, 0));
+    assert_eq!(canvas.ink(), 4 * 11);
+
+    // Partly off screen
+    canvas.clear();
+    canvas.draw_text(&font, "II\nII", 30., 10., 2, (255, 0, 0));
+    assert!(canvas.ink() > 0);
+}
+
+#[test]
+fn grid_fonts() {
+    let font = Font::from_grid(&atlas(), 3, 3, 'a');
+    assert_eq!(font.measure("ab"), (6, 3));
+    let dot = *font.glyph('b').unwrap();
+    assert!(font.is_ink(&dot, 1, 1));
+    assert!(!font.is_ink(&dot, 0, 0));
+
+    let mut canvas = Canvas::new(10, 10);
+    canvas.draw_text(&font, "ab", 1., 1., 1, (0, 255, 0));
+    assert_eq!(canvas.ink(), 9 + 1);
+}
+
+const FNT: &str = r#"info face="Test" size=3
+common lineHeight=4 base=3 scaleW=6 scaleH=3 pages=1
+page id=0 file="test.png"
+chars count=2
+char id=65 x=0 y=0 width=3 height=3 xoffset=0 yoffset=1 xadvance=4 page=0 chnl=15
+char id=63 x=3 y=0 width=3 height=3 xoffset=0 yoffset=0 xadvance=3 page=0 chnl=15
+"#;
+
+#[test]
+fn bmfont_files() {
+    assert_eq!(Font::bmfont_page(FNT), Some("test.png"));
+    let font = Font::from_bmfont(FNT, &atlas()).unwrap();
+    assert_eq!(font.line_height, 4);
+    assert_eq!(font.glyph('A').unwrap().y_offset, 1);
+    assert_eq!(font.measure("AAz"), (11, 4));
+
+    assert!(Font::from_bmfont("common lineHeight=4", &atlas()).is_err());
+    assert!(Font::from_bmfont(&FNT.replace("x=3", "x=5"), &atlas()).is_err());
+    assert!(Font::from_bmfont(&FNT.replace("x=3", "x=-3"), &atlas()).is_err());
+    assert!(Font::from_bmfont(&FNT.replace("width=3 height=3 xoffset=0 yoffset=1", "width=-1 height=3 xoffset=0 yoffset=1"), &atlas()).is_err());
+    assert!(Font::from_bmfont(&FNT.replace("lineHeight=4", "lineHeight=-4"), &atlas()).is_err());
+    assert!(Font::from_bmfont(&FNT.replace("x=3", "x=9223372036854775807"), &atlas()).is_err());
+}
+
+#[test]
+fn fonts_are_assets() {
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgba8(atlas()).write_to(&mut png, ImageOutputFormat::Png).unwrap();
+    let index = br#"[ { "path": "fonts/test.fnt", "type": "Font", "entity_id": null } ]"#;
+    let source = AssetSource::memory(vec![
+        ("index.json", index.to_vec()),
+        ("fonts/test.fnt", FNT.as_bytes().to_vec()),
+        ("fonts/test.png", png),
+    ]);
+
+    let mut world = World::new();
+    let assets = block_on(assets::load_assets(&source)).unwrap();
+    block_on(assets::import_assets(&source, assets, &mut world)).unwrap();
+    assert_eq!(world.fonts["fonts/test.fnt"].line_height, 4);
+}
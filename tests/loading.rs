@@ -0,0 +1,69 @@
+//! Loading every asset at once, with progress.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{assets::{self, AssetSource, Progress}, world::*};
+use futures::executor::block_on;
+
+mod common;
+use common::{source, INDEX};
+
+fn import(source: &AssetSource) -> (World, Vec<Progress>) {
+    let assets = block_on(assets::load_assets(source)).unwrap();
+    let mut world = World::new();
+    let mut reports = Vec::new();
+    block_on(assets::import_assets_with_progress(source, assets, &mut world, |p| reports.push(p))).unwrap();
+    (world, reports)
+}
+
+#[test]
+fn progress_counts_items_and_bytes() {
+    let source = source();
+    let (_, reports) = import(&source);
+    assert_eq!(reports.len(), 4);
+
+    let first = reports[0];
+    assert_eq!((first.loaded_items, first.total_items, first.loaded_bytes), (0, 3, 0));
+    assert_eq!(first.fraction(), 0.);
+    assert!(!first.is_done());
+    for pair in reports.windows(2) {
+        assert!(pair[1].loaded_bytes > pair[0].loaded_bytes);
+        assert!(pair[1].fraction() >= pair[0].fraction());
+    }
+
+    // The tileset is only found once the map is read
+    let files = ["player.png", "levels/one.json", "levels/tiles.png", "scripts/bounce.rhai"];
+    let all: u64 = files.iter().map(|path| source.size(path).unwrap()).sum();
+    let last = *reports.last().unwrap();
+    assert!(first.total_bytes < all);
+    assert_eq!((last.loaded_items, last.loaded_bytes, last.total_bytes), (3, all, all));
+    assert_eq!(last.fraction(), 1.);
+    assert!(last.is_done());
+}
+
+#[test]
+fn assets_are_added_in_index_order() {
+    let (world, _) = import(&source());
+    let c = &world.components;
+    let crate_ = c.properties.iter().find(|(_, p)| p.get("name").map(String::as_str) == Some("crate")).unwrap().0;
+    assert_eq!(c.script.get(crate_).unwrap().path, "scripts/bounce.rhai");
+    assert!(c.sprite.get(world.player().unwrap()).and_then(|s| s.texture.as_ref()).is_some());
+}
+
+#[test]
+fn failures_stop_loading() {
+    let source = AssetSource::memory(vec![("index.json", INDEX.as_bytes().to_vec())]);
+    let assets = block_on(assets::load_assets(&source)).unwrap();
+    let mut world = World::new();
+    let error = block_on(assets::import_assets_with_progress(&source, assets, &mut world, |_| {})).unwrap_err();
+    // The message survives loading on another thread
+    assert!(error.to_string().starts_with("Missing asset file: "));
+    assert!(world.tilemaps.is_empty());
+}
+
+#[test]
+fn unknown_sizes_count_items() {
+    let progress = Progress { loaded_items: 1, total_items: 4, loaded_bytes: 100, total_bytes: 0 };
+    assert_eq!(progress.fraction(), 0.25);
+    assert_eq!(Progress::default().fraction(), 1.);
+}
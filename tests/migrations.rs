@@ -0,0 +1,75 @@
+//! Loading saves written by every historical version of the save format.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{save, save::migrations, world::*};
+
+fn fixture(version: u32) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/save_v{}.json", env!("CARGO_MANIFEST_DIR"), version);
+    std::fs::read(&path).unwrap_or_else(|_| panic!("missing fixture {}", path))
+}
+
+/// Every fixture holds the same world, so they all have to load identically
+fn check(world: &World) {
+    let c = &world.components;
+    assert_eq!(world.player(), Some(0));
+    assert_eq!(c.player.get(0), Some(&PlayerControlled { input: 0 }));
+    assert_eq!(c.position.get(0), Some(&Coord { x: 40., y: 24., z: 0. }));
+    assert_eq!(c.sprite.get(0).and_then(|s| s.path.as_deref()), Some("player.png"));
+
+    assert_eq!(world.entities.len(), 2);
+    assert_eq!(c.position.get(1), Some(&Coord { x: 96., y: 24., z: 0. }));
+    assert!(c.collider.get(1).unwrap().pushable);
+    assert_eq!(c.collider.get(1).unwrap().mass, 2.);
+    assert_eq!(c.properties.get(1).unwrap()["loot"], "coin");
+    assert!(c.sprite.get(1).is_none());
+
+    assert_eq!(world.tilemaps.len(), 1);
+    assert_eq!(world.tilemaps[0].tiles, vec![Some(0), Some(1), None, None, Some(1), Some(0)]);
+    assert!(world.tilemaps[0].is_solid(1, 0));
+    assert_eq!(world.tilemaps[0].tileset_path.as_deref(), Some("tiles.png"));
+
+    assert_eq!(world.boundary.end.x, 48.);
+    assert_eq!(world.scroll, (10., -4.));
+}
+
+#[test]
+fn every_version_has_a_migration() {
+    assert_eq!(save::SAVE_VERSION as usize, migrations::MIGRATIONS.len() + 1);
+}
+
+#[test]
+fn every_version_loads() {
+    for version in 1..=save::SAVE_VERSION {
+        let data = fixture(version);
+        let document = serde_json::from_slice(&data).unwrap();
+        assert_eq!(migrations::version(&document), Some(version));
+
+        let world = save::deserialize(&data)
+            .unwrap_or_else(|e| panic!("version {} does not load: {}", version, e));
+        check(&world);
+    }
+}
+
+#[test]
+fn migrated_documents_match_current_format() {
+    let current = serde_json::from_slice::<serde_json::Value>(&fixture(save::SAVE_VERSION)).unwrap();
+    for version in 1..save::SAVE_VERSION {
+        let document = serde_json::from_slice(&fixture(version)).unwrap();
+        assert_eq!(migrations::migrate(document).unwrap(), current, "version {}", version);
+    }
+}
+
+#[test]
+fn current_saves_load_without_migration() {
+    let world = save::deserialize(&fixture(save::SAVE_VERSION)).unwrap();
+    let data = save::serialize(&world).unwrap();
+    let document = serde_json::from_slice(&data).unwrap();
+    assert_eq!(migrations::version(&document), Some(save::SAVE_VERSION));
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let document = serde_json::json!({ "header": { "version": save::SAVE_VERSION + 1 }, "world": {} });
+    assert!(migrations::migrate(document).is_err());
+}
@@ -0,0 +1,128 @@
+//! A* paths on a `NavGrid`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::world::*;
+
+fn small_hitbox() -> Region {
+    Region {
+        start: Coord { x: -4., y: -4., z: 8. },
+        end: Coord { x: 4., y: 4., z: 0. },
+    }
+}
+
+/// A 160x160 world, with the player out of the way in a corner
+fn world() -> World {
+    let mut world = World::new();
+    world.boundary.start = Coord { x: 0., y: 0., z: 1000. };
+    world.boundary.end = Coord { x: 160., y: 160., z: 0. };
+    let player = world.player().unwrap();
+    world.components.position.insert(player, Coord { x: 150., y: 150., z: 0. });
+    world
+}
+
+/// A solid wall at x = 64..96 from the top down to y = 128
+fn wall(world: &mut World) -> EntityId {
+    let id = world.spawn_body(Coord { x: 80., y: 64., z: 0. });
+    let collider = world.components.collider.get_mut(id).unwrap();
+    collider.hitbox.start.y = -64.;
+    collider.hitbox.end.y = 64.;
+    id
+}
+
+#[test]
+fn straight_line_on_an_empty_grid() {
+    let world = world();
+    let grid = NavGrid::build(&world, 16., small_hitbox());
+    assert_eq!((grid.columns, grid.rows), (10, 10));
+
+    let path = grid.find_path(Coord { x: 8., y: 8., z: 0. }, Coord { x: 72., y: 8., z: 0. }).unwrap();
+    assert_eq!(path.len(), 4);
+    assert!(path.iter().all(|p| p.y == 8.));
+    assert_eq!(*path.last().unwrap(), Coord { x: 72., y: 8., z: 0. });
+}
+
+#[test]
+fn paths_go_around_walls() {
+    let mut world = world();
+    wall(&mut world);
+    let grid = NavGrid::build(&world, 16., small_hitbox());
+
+    let (from, to) = (Coord { x: 24., y: 24., z: 0. }, Coord { x: 136., y: 24., z: 0. });
+    let path = grid.find_path(from, to).unwrap();
+    assert!(path.iter().any(|p| p.y > 128.), "{:?}", path);
+    for waypoint in path.iter() {
+        let (column, row) = grid.cell_of(*waypoint).unwrap();
+        assert!(!grid.is_blocked(column, row));
+    }
+}
+
+#[test]
+fn bigger_hitboxes_need_wider_gaps() {
+    let mut world = world();
+    wall(&mut world);
+    // The gap below the wall is 32 high, too narrow for a 40x40 entity
+    let (from, to) = (Coord { x: 24., y: 24., z: 0. }, Coord { x: 136., y: 24., z: 0. });
+    let mut big = small_hitbox();
+    big.start.x = -20.;
+    big.start.y = -20.;
+    big.end.x = 20.;
+    big.end.y = 20.;
+
+    assert!(NavGrid::build(&world, 16., small_hitbox()).find_path(from, to).is_some());
+    assert!(NavGrid::build(&world, 8., big).find_path(from, to).is_none());
+}
+
+#[test]
+fn solid_tiles_are_obstacles() {
+    let mut world = world();
+    let mut tilemap = Tilemap::new(10, 10, (16, 16));
+    tilemap.solid = vec![true];
+    for row in 0..10 {
+        tilemap.set(5, row, Some(0));
+    }
+    world.tilemaps.push(tilemap);
+
+    let grid = NavGrid::build(&world, 16., small_hitbox());
+    assert!(grid.is_blocked(5, 3));
+    assert!(!grid.is_blocked(4, 3));
+    assert!(grid.find_path(Coord { x: 8., y: 8., z: 0. }, Coord { x: 152., y: 8., z: 0. }).is_none());
+}
+
+#[test]
+fn moving_entities_are_not_obstacles() {
+    let mut world = world();
+    let id = wall(&mut world);
+    world.components.ai.insert(id, Ai::new(Behaviour::Idle));
+
+    let grid = NavGrid::build(&world, 16., small_hitbox());
+    let (column, row) = grid.cell_of(Coord { x: 80., y: 64., z: 0. }).unwrap();
+    assert!(!grid.is_blocked(column, row));
+}
+
+#[test]
+fn obstacle_revision_changes_with_obstacles() {
+    let mut world = world();
+    let start = world.obstacle_revision();
+
+    // Players and other moving entities do not matter
+    let player = world.player().unwrap();
+    world.components.velocity.get_mut(player).unwrap().to.x = 3.;
+    world.tick();
+    assert_eq!(world.obstacle_revision(), start);
+
+    let id = wall(&mut world);
+    let spawned = world.obstacle_revision();
+    assert_ne!(spawned, start);
+    world.tick();
+    assert_eq!(world.obstacle_revision(), spawned);
+
+    world.components.velocity.get_mut(id).unwrap().to.x = 16.;
+    world.tick();
+    let moved = world.obstacle_revision();
+    assert_ne!(moved, spawned);
+
+    world.despawn(id);
+    assert_ne!(world.obstacle_revision(), moved);
+    assert_ne!(World::new().obstacle_revision(), world.obstacle_revision());
+}
@@ -0,0 +1,90 @@
+//! Asset packs.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{assets::{self, pack::{AssetPack, PackEntry}, AssetSource}, world::*};
+use futures::executor::block_on;
+use image::{ImageOutputFormat, RgbaImage};
+
+fn png(width: u32, height: u32) -> Vec<u8> {
+    let mut data = Vec::new();
+    image::DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+        .write_to(&mut data, ImageOutputFormat::Png)
+        .unwrap();
+    data
+}
+
+#[test]
+fn files_round_trip() {
+    let text = "hello ".repeat(100);
+    let data = AssetPack::build(vec![("a.txt", text.as_bytes()), ("dir/empty", &[][..])]);
+    assert!(data.len() < text.len());
+
+    let pack = AssetPack::from_bytes(data).unwrap();
+    assert_eq!(pack.read("a.txt").unwrap(), text.as_bytes());
+    assert!(pack.read("dir/empty").unwrap().is_empty());
+    assert!(pack.read("missing").is_err());
+    assert_eq!(pack.entries().count(), 2);
+}
+
+#[test]
+fn damaged_packs_are_rejected() {
+    let data = AssetPack::build(vec![("a.txt", &b"some text"[..])]);
+    assert!(AssetPack::from_bytes(b"not a pack at all".to_vec()).is_err());
+    assert!(AssetPack::from_bytes(data[..data.len() - 2].to_vec()).is_err());
+
+    let mut version = data.clone();
+    version[4] = 99;
+    assert!(AssetPack::from_bytes(version).is_err());
+}
+
+/// Pack of `files`, with `change` applied to its index
+fn with_index(files: Vec<(&str, &[u8])>, change: impl FnOnce(&mut Vec<PackEntry>)) -> Vec<u8> {
+    let data = AssetPack::build(files);
+    let index_end = 12 + u32::from_le_bytes([data[8], data[9], data[10], data[11]]) as usize;
+    let mut index: Vec<PackEntry> = serde_json::from_slice(&data[12..index_end]).unwrap();
+    change(&mut index);
+
+    let index = serde_json::to_vec(&index).unwrap();
+    let mut pack = data[..8].to_vec();
+    pack.extend_from_slice(&(index.len() as u32).to_le_bytes());
+    pack.extend_from_slice(&index);
+    pack.extend_from_slice(&data[index_end..]);
+    pack
+}
+
+#[test]
+fn files_inflating_past_their_length_are_rejected() {
+    let zeros = vec![0; 1 << 20];
+    let data = with_index(vec![("a.bin", &zeros)], |index| index[0].length = 100);
+
+    let pack = AssetPack::from_bytes(data).unwrap();
+    let error = pack.read("a.bin").unwrap_err().to_string();
+    assert!(error.contains("does not decompress"), "{}", error);
+}
+
+#[test]
+fn impossible_lengths_are_rejected() {
+    let text = "x".repeat(900);
+    let data = with_index(vec![("a.txt", text.as_bytes())], |index| index[0].length = u64::MAX);
+
+    let pack = AssetPack::from_bytes(data).unwrap();
+    let error = pack.read("a.txt").unwrap_err().to_string();
+    assert!(error.contains("claims more data"), "{}", error);
+}
+
+#[test]
+fn assets_are_imported_from_the_pack() {
+    let index = br#"[ { "path": "sprites/player.png", "type": "Texture", "entity_id": "player" } ]"#;
+    let player = png(10, 6);
+    let data = AssetPack::build(vec![("index.json", &index[..]), ("sprites/player.png", &player)]);
+
+    let source = AssetSource::pack(data).unwrap();
+    let assets = block_on(assets::load_assets(&source)).unwrap();
+    let mut world = World::new();
+    block_on(assets::import_assets(&source, assets, &mut world)).unwrap();
+
+    let id = world.player().unwrap();
+    let texture = world.components.sprite.get(id).and_then(|s| s.texture.as_ref()).unwrap();
+    assert_eq!((texture.width(), texture.height()), (10, 6));
+}
@@ -0,0 +1,74 @@
+//! Several player controlled entities sharing the keyboard.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{engine::{MovementKeys, MOVEMENT_KEYS}, world::*};
+
+mod common;
+use common::pos;
+
+#[test]
+fn players_get_separate_inputs() {
+    let mut world = World::new();
+    let first = world.player().unwrap();
+    let second = world.spawn_player(Coord { x: 100., y: 0., z: 0. });
+
+    let c = &world.components;
+    assert_eq!(c.player.get(first).unwrap().input, 0);
+    assert_eq!(c.player.get(second).unwrap().input, 1);
+    assert_eq!(world.players().collect::<Vec<_>>(), vec![first, second]);
+
+    // Freed mappings are handed out again
+    world.despawn(first);
+    let third = world.spawn_player(Coord::origin());
+    assert_eq!(world.components.player.get(third).unwrap().input, 0);
+}
+
+#[test]
+fn each_player_follows_its_own_keys() {
+    let mut world = World::new();
+    let first = world.player().unwrap();
+    let second = world.spawn_player(Coord { x: 100., y: 0., z: 0. });
+
+    unsafe {
+        MOVEMENT_KEYS[1] = MovementKeys { right: true, ..MovementKeys::new() };
+    }
+    world.tick();
+    unsafe {
+        MOVEMENT_KEYS[1] = MovementKeys::new();
+    }
+
+    assert_eq!(pos(&world, first).x, 0.);
+    assert!(pos(&world, second).x > 100.);
+}
+
+#[test]
+fn camera_follows_a_single_player_like_before() {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.position.get_mut(player).unwrap().x = 400.;
+
+    world.scroll((320., 240.), (640., 480.));
+
+    // 400 - scroll + 320 = 640 * 0.85
+    assert!((world.scroll.0 - 176.).abs() < 1e-9, "{}", world.scroll.0);
+    assert_eq!(world.scroll.1, 0.);
+}
+
+#[test]
+fn camera_frames_every_player() {
+    let mut world = World::new();
+    let first = world.player().unwrap();
+    world.components.position.get_mut(first).unwrap().x = -300.;
+    let second = world.spawn_player(Coord { x: 100., y: 0., z: 0. });
+
+    world.scroll((320., 240.), (640., 480.));
+    let on_screen = |x: f64| x - world.scroll.0 + 320.;
+    assert!(on_screen(pos(&world, first).x) >= 640. * 0.15 - 1e-9);
+    assert!(on_screen(pos(&world, second).x) <= 640. * 0.85 + 1e-9);
+
+    // Too far apart to fit, so the camera centers between them
+    world.components.position.get_mut(second).unwrap().x = 500.;
+    world.scroll((320., 240.), (640., 480.));
+    assert_eq!(world.scroll.0, 100.);
+}
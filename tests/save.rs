@@ -0,0 +1,112 @@
+//! Saving and loading the `World`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{save, world::*};
+use image::RgbaImage;
+
+/// A world with its player and a crate
+fn world() -> (World, EntityId, EntityId) {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.position.insert(player, Coord { x: 12., y: -3.5, z: 2. });
+    world.components.velocity.get_mut(player).unwrap().to.x = 1.25;
+    world.set_texture(player, Some(RgbaImage::new(8, 4)), Some("player.png".to_string()));
+
+    let crate_ = world.spawn_body(Coord { x: 64., y: 0., z: 0. });
+    let c = &mut world.components;
+    c.collider.get_mut(crate_).unwrap().pushable = true;
+    c.collider.get_mut(crate_).unwrap().mass = 3.;
+    c.velocity.get_mut(crate_).unwrap().damping = Damping::Exponential;
+    let mut properties = Properties::new();
+    properties.insert("loot".to_string(), "coin".to_string());
+    c.properties.insert(crate_, properties);
+
+    let mut tilemap = Tilemap::new(2, 1, (16, 16));
+    tilemap.set(1, 0, Some(3));
+    tilemap.solid = vec![false, false, false, true];
+    tilemap.set_tileset(Some(RgbaImage::new(64, 16)));
+    tilemap.tileset_path = Some("tiles.png".to_string());
+    world.tilemaps.push(tilemap);
+
+    world.scroll = (5., 6.);
+    world.boundary.end.x = 500.;
+    (world, player, crate_)
+}
+
+#[test]
+fn round_trip_keeps_state() {
+    let (original, player, crate_) = world();
+    let data = save::serialize(&original).unwrap();
+    let loaded = save::deserialize(&data).unwrap();
+    let (a, b) = (&original.components, &loaded.components);
+
+    assert_eq!(loaded.entities, original.entities);
+    assert_eq!(loaded.player(), Some(player));
+    assert_eq!(b.position.get(player), a.position.get(player));
+    assert_eq!(b.velocity.get(player).unwrap().to, a.velocity.get(player).unwrap().to);
+    assert_eq!(b.collider.get(player), a.collider.get(player));
+    assert_eq!(b.velocity.get(crate_).unwrap().damping, Damping::Exponential);
+    assert_eq!(b.properties.get(crate_).unwrap()["loot"], "coin");
+    assert!(b.collider.get(crate_).unwrap().pushable);
+    assert_eq!(loaded.tilemaps[0].tiles, original.tilemaps[0].tiles);
+    assert!(loaded.tilemaps[0].is_solid(1, 0));
+    assert_eq!(loaded.scroll, original.scroll);
+    assert_eq!(loaded.boundary, original.boundary);
+
+    assert_eq!(save::serialize(&loaded).unwrap(), data);
+}
+
+#[test]
+fn textures_are_saved_as_paths() {
+    let (world, player, _) = world();
+    let data = save::serialize(&world).unwrap();
+    let loaded = save::deserialize(&data).unwrap();
+
+    let sprite = loaded.components.sprite.get(player).unwrap();
+    assert!(sprite.texture.is_none());
+    assert_eq!(sprite.path.as_deref(), Some("player.png"));
+    assert!(loaded.tilemaps[0].tileset.is_none());
+    assert!(data.len() < 4096);
+}
+
+#[test]
+fn restore_reuses_loaded_textures() {
+    let (mut saved, player, _) = world();
+    saved.components.position.get_mut(player).unwrap().x = 99.;
+    let saved = save::deserialize(&save::serialize(&saved).unwrap()).unwrap();
+
+    let (mut current, _, _) = world();
+    save::restore(&mut current, saved);
+
+    let c = &current.components;
+    assert_eq!(c.position.get(player).unwrap().x, 99.);
+    let texture = c.sprite.get(player).and_then(|s| s.texture.as_ref());
+    assert_eq!(texture.map(|t| t.width()), Some(8));
+    assert_eq!(current.tilemaps[0].tileset.as_ref().map(|t| t.width()), Some(64));
+}
+
+#[test]
+fn new_entities_do_not_reuse_loaded_ids() {
+    let data = save::serialize(&world().0).unwrap();
+    let mut loaded = save::deserialize(&data).unwrap();
+    let newest = loaded.entities.iter().copied().max().unwrap();
+    assert!(loaded.spawn() > newest);
+}
+
+#[test]
+fn unknown_versions_are_rejected() {
+    let data = String::from_utf8(save::serialize(&world().0).unwrap()).unwrap();
+    let data = data.replacen(&format!("\"version\":{}", save::SAVE_VERSION), "\"version\":999", 1);
+    assert!(save::deserialize(data.as_bytes()).is_err());
+}
+
+#[test]
+fn names_cannot_leave_the_save_directory() {
+    let (mut world, _, _) = world();
+    for name in &["", "../escape", "nested/slot", "nested\\slot", ".."] {
+        assert!(save::save(&world, name).is_err(), "{:?} was saved", name);
+        assert!(save::load(&mut world, name).is_err(), "{:?} was loaded", name);
+    }
+    assert!(!std::path::Path::new("./escape.json").exists());
+}
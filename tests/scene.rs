@@ -0,0 +1,195 @@
+//! The scene stack driving the game.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{
+    engine::{Engine, Pixel},
+    scene::*,
+    world::*,
+};
+use std::{cell::RefCell, rc::Rc};
+
+/// Engine drawing into a plain pixel buffer
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![(0, 0, 0); width * height] }
+    }
+}
+
+impl Engine for Canvas {
+    fn width(&self) -> usize {
+        self.width
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn clear(&mut self) {
+        self.pixels = vec![(0, 0, 0); self.width * self.height];
+    }
+    fn set_at(&mut self, idx: usize, pixel: Pixel) {
+        self.pixels[idx] = pixel;
+    }
+    fn set_at_with_opacity(&mut self, idx: usize, pixel: Pixel, _opacity: f64) {
+        self.pixels[idx] = pixel;
+    }
+}
+
+/// Scene noting its name when drawn, and doing what the test tells it on update
+struct Named {
+    name: &'static str,
+    overlay: bool,
+    drawn: Rc<RefCell<Vec<&'static str>>>,
+    next: Option<Transition>,
+}
+
+impl Scene for Named {
+    fn update(&mut self, _world: &mut World, _input: &Input) -> Transition {
+        self.next.take().unwrap_or(Transition::None)
+    }
+
+    fn render(&mut self, _world: &World, _engine: &mut dyn Engine) {
+        self.drawn.borrow_mut().push(self.name);
+    }
+
+    fn is_overlay(&self) -> bool {
+        self.overlay
+    }
+}
+
+fn named(name: &'static str, overlay: bool, drawn: &Rc<RefCell<Vec<&'static str>>>, next: Option<Transition>) -> Box<Named> {
+    Box::new(Named { name, overlay, drawn: drawn.clone(), next })
+}
+
+fn input(commands: &[Command]) -> Input {
+    Input { commands: commands.to_vec(), ..Input::new((640., 480.)) }
+}
+
+/// Names of the scenes drawn by one `render()`
+fn render(stack: &mut SceneStack, world: &World, drawn: &Rc<RefCell<Vec<&'static str>>>) -> Vec<&'static str> {
+    drawn.borrow_mut().clear();
+    stack.render(world, &mut Canvas::new(16, 16));
+    drawn.borrow().clone()
+}
+
+#[test]
+fn transitions_change_the_stack() {
+    let drawn = Rc::new(RefCell::new(Vec::new()));
+    let mut world = World::new();
+    let menu = named("menu", true, &drawn, Some(Transition::Pop));
+    let game = named("game", false, &drawn, Some(Transition::Push(menu)));
+    let mut stack = SceneStack::new(named("title", false, &drawn, Some(Transition::Replace(game))));
+
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(render(&mut stack, &world, &drawn), ["game"]);
+
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(stack.len(), 2);
+    assert_eq!(render(&mut stack, &world, &drawn), ["game", "menu"]);
+
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(render(&mut stack, &world, &drawn), ["game"]);
+
+    stack.push(named("quit", false, &drawn, Some(Transition::Quit)));
+    assert_eq!(render(&mut stack, &world, &drawn), ["quit"]);
+    stack.update(&mut world, &input(&[]));
+    assert!(stack.is_empty());
+    assert!(render(&mut stack, &world, &drawn).is_empty());
+}
+
+#[test]
+fn overlays_show_the_scenes_below() {
+    let drawn = Rc::new(RefCell::new(Vec::new()));
+    let world = World::new();
+    let mut stack = SceneStack::new(named("hidden", false, &drawn, None));
+    stack.push(named("game", false, &drawn, None));
+    stack.push(named("pause", true, &drawn, None));
+    stack.push(named("confirm", true, &drawn, None));
+    assert_eq!(render(&mut stack, &world, &drawn), ["game", "pause", "confirm"]);
+}
+
+fn player_x(world: &World) -> f64 {
+    world.components.position.get(world.player().unwrap()).unwrap().x
+}
+
+#[test]
+fn pausing_freezes_the_world() {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.velocity.get_mut(player).unwrap().to.x = 2.;
+    // Below the menus
+    world.components.position.get_mut(player).unwrap().y = 150.;
+    let mut stack = SceneStack::game();
+
+    // The title screen shows the world standing still
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(player_x(&world), 0.);
+    stack.update(&mut world, &input(&[Command::Confirm]));
+    stack.update(&mut world, &input(&[]));
+    let moved = player_x(&world);
+    assert!(moved > 0.);
+
+    stack.update(&mut world, &input(&[Command::Pause]));
+    assert_eq!(stack.len(), 2);
+    stack.update(&mut world, &input(&[]));
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(player_x(&world), moved);
+
+    // The game is still drawn below the menu while paused
+    let mut canvas = Canvas::new(640, 480);
+    stack.render(&world, &mut canvas);
+    assert!(canvas.pixels.contains(&(255, 0, 255)));
+    assert!(canvas.pixels.contains(&(64, 64, 96)));
+
+    stack.update(&mut world, &input(&[Command::Pause]));
+    assert_eq!(stack.len(), 1);
+    stack.update(&mut world, &input(&[]));
+    assert!(player_x(&world) > moved);
+}
+
+#[test]
+fn menus_are_clicked() {
+    let mut world = World::new();
+    let mut stack = SceneStack::game();
+    let mut click = input(&[]);
+
+    // Nothing to click in the corner
+    click.clicks.push((0., 0.));
+    stack.update(&mut world, &click);
+    stack.update(&mut world, &input(&[Command::Confirm]));
+    stack.update(&mut world, &input(&[Command::Pause]));
+    assert_eq!(stack.len(), 2);
+
+    // The first button is right below the middle of the screen
+    click.clicks = vec![(320., 240. + 8.)];
+    stack.update(&mut world, &click);
+    assert_eq!(stack.len(), 1);
+}
+
+#[test]
+fn game_over_starts_over() {
+    let mut world = World::new();
+    let crate_id = world.spawn_body(Coord { x: 100., y: 0., z: 0. });
+    let mut stack = SceneStack::game();
+    stack.update(&mut world, &input(&[Command::Confirm]));
+    stack.update(&mut world, &input(&[]));
+
+    world.despawn(world.player().unwrap());
+    world.despawn(crate_id);
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(stack.len(), 1);
+    assert!(world.player().is_none());
+
+    // Game over waits, then goes back to the title screen with the world as it started
+    stack.update(&mut world, &input(&[]));
+    stack.update(&mut world, &input(&[Command::Confirm]));
+    assert_eq!(world.entities.len(), 2);
+    assert_eq!(player_x(&world), 0.);
+    stack.update(&mut world, &input(&[]));
+    assert_eq!(player_x(&world), 0.);
+}
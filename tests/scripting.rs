@@ -0,0 +1,102 @@
+//! Rhai scripts attached to entities.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::world::{scripting, *};
+use std::sync::Arc;
+
+mod common;
+use common::pos;
+
+/// World with a script loaded as `test.rhai`, and a resting entity at the origin running it
+fn world(source: &str) -> (World, EntityId) {
+    let mut world = World::new();
+    let player = world.player().unwrap();
+    world.components.position.insert(player, Coord { x: 0., y: 500., z: 0. });
+
+    let ast = scripting::compile(source).unwrap();
+    world.scripts.insert("test.rhai".to_string(), Arc::new(ast));
+    let id = world.spawn_body(Coord::origin());
+    world.components.velocity.get_mut(id).unwrap().falloff = Coord::origin();
+    world.components.script.insert(id, Script::new("test.rhai"));
+    (world, id)
+}
+
+#[test]
+fn scripts_change_velocity() {
+    let (mut world, id) = world("
+        fn tick(world, id) {
+            let vel = world.velocity(id);
+            vel.x = 2.0;
+            world.set_velocity(id, vel);
+        }
+    ");
+    world.tick();
+    world.tick();
+    assert_eq!(pos(&world, id).x, 4.);
+    assert_eq!(world.components.script.get(id).unwrap().error, None);
+}
+
+#[test]
+fn scripts_spawn_entities() {
+    let (mut world, id) = world("
+        fn tick(world, id) {
+            let pos = world.position(id);
+            if pos.x == 0.0 {
+                world.spawn_body(coord(100.0, 0.0, 0.0));
+                world.set_position(id, coord(1.0, 0.0, 0.0));
+            }
+        }
+    ");
+    let before = world.entities.len();
+    world.tick();
+    world.tick();
+    assert_eq!(world.entities.len(), before + 1);
+
+    let spawned = *world.entities.iter().max().unwrap();
+    assert!(spawned > id);
+    assert_eq!(pos(&world, spawned).x, 100.);
+}
+
+#[test]
+fn scripts_see_collisions() {
+    let (mut world, id) = world("
+        fn tick(world, id) {
+            for other in world.collisions(id) {
+                world.set_position(other, coord(-500.0, -500.0, 0.0));
+            }
+        }
+    ");
+    let other = world.spawn_body(Coord { x: 20., y: 0., z: 0. });
+
+    // The overlap is only known after the collision system ran
+    world.tick();
+    assert_ne!(pos(&world, other).x, -500.);
+    world.tick();
+    assert_eq!(pos(&world, other).x, -500.);
+    assert_eq!(pos(&world, id).y, 0.);
+}
+
+#[test]
+fn errors_are_kept_on_the_component() {
+    let (mut world, id) = world("fn tick(world, id) { world.no_such_function(); }");
+    world.tick();
+    assert!(world.components.script.get(id).unwrap().error.is_some());
+
+    world.components.script.insert(id, Script::new("missing.rhai"));
+    world.tick();
+    assert!(world.components.script.get(id).unwrap().error.as_ref().unwrap().contains("missing.rhai"));
+}
+
+#[test]
+fn endless_loops_are_stopped() {
+    let (mut world, id) = world("fn tick(world, id) { loop { } }");
+    world.tick();
+    assert!(world.components.script.get(id).unwrap().error.is_some());
+    assert!(!world.components.position.is_empty());
+}
+
+#[test]
+fn syntax_errors_fail_to_compile() {
+    assert!(scripting::compile("fn tick(world, id) {").is_err());
+}
@@ -0,0 +1,61 @@
+//! Reading assets from different sources.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{assets::{self, pack::AssetPack, AssetSource}, embedded_assets, world::*};
+use futures::executor::block_on;
+use std::fs;
+
+fn import(source: &AssetSource) -> World {
+    let mut world = World::new();
+    let assets = block_on(assets::load_assets(source)).unwrap();
+    block_on(assets::import_assets(source, assets, &mut world)).unwrap();
+    world
+}
+
+fn player_texture_size(world: &World) -> (u32, u32) {
+    let id = world.player().unwrap();
+    let texture = world.components.sprite.get(id).and_then(|s| s.texture.as_ref()).unwrap();
+    (texture.width(), texture.height())
+}
+
+#[test]
+fn sources_give_the_same_assets() {
+    let index = fs::read("assets/index.json").unwrap();
+    let player = fs::read("assets/player.png").unwrap();
+    let pack = AssetPack::build(vec![("index.json", &index[..]), ("player.png", &player[..])]);
+
+    let sources = [
+        AssetSource::directory("assets"),
+        embedded_assets!("../assets", "index.json", "player.png"),
+        AssetSource::memory(vec![("index.json", index.clone()), ("player.png", player.clone())]),
+        AssetSource::pack(pack).unwrap(),
+    ];
+    let expected = player_texture_size(&import(&sources[0]));
+    for source in sources.iter() {
+        assert_eq!(player_texture_size(&import(source)), expected);
+    }
+}
+
+#[test]
+fn missing_files_are_errors() {
+    let source = AssetSource::memory(vec![("index.json", b"[]".to_vec())]);
+    assert_eq!(block_on(source.read("index.json")).unwrap(), b"[]");
+    let error = block_on(source.read("player.png")).unwrap_err();
+    assert_eq!(error.to_string(), "Missing asset file: player.png");
+
+    let source = AssetSource::directory("no/such/dir");
+    assert!(block_on(assets::load_assets(&source)).is_err());
+}
+
+#[test]
+fn open_tells_packs_from_directories() {
+    assert!(AssetSource::open("assets").unwrap().root().is_some());
+
+    let path = std::env::temp_dir().join(format!("canvasgame-source-{}.pack", std::process::id()));
+    fs::write(&path, AssetPack::build(vec![("index.json", &b"[]"[..])])).unwrap();
+    let source = AssetSource::open(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert!(source.root().is_none());
+    assert!(block_on(assets::load_assets(&source)).unwrap().is_empty());
+}
@@ -0,0 +1,169 @@
+//! Importing Tiled JSON maps.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{assets::tiled::TiledMap, world::*};
+
+const MAP: &str = r#"{
+    "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+    "orientation": "orthogonal", "infinite": false,
+    "layers": [
+        { "type": "tilelayer", "name": "ground", "width": 3, "height": 2, "data": [1, 2, 0, 0, 2, 5] },
+        { "type": "group", "layers": [
+            { "type": "objectgroup", "name": "objects", "objects": [
+                { "id": 1, "name": "hero", "type": "player", "x": 8, "y": 8, "width": 0, "height": 0 },
+                { "id": 2, "name": "crate", "type": "box", "x": 16, "y": 0, "width": 16, "height": 8,
+                  "properties": [
+                    { "name": "pushable", "type": "bool", "value": true },
+                    { "name": "mass", "type": "float", "value": 2.5 },
+                    { "name": "loot", "type": "string", "value": "coin" }
+                  ] }
+            ] }
+        ] }
+    ],
+    "tilesets": [
+        { "firstgid": 1, "image": "tiles.png", "tilecount": 4, "tilewidth": 16, "tileheight": 16,
+          "tiles": [ { "id": 1, "properties": [ { "name": "solid", "type": "bool", "value": true } ] } ] },
+        { "firstgid": 5, "image": "more.png", "tilecount": 1, "tilewidth": 16, "tileheight": 16 }
+    ]
+}"#;
+
+fn world() -> World {
+    let map = TiledMap::from_slice(MAP.as_bytes()).unwrap();
+    assert_eq!(map.tileset_images(), vec![Some("tiles.png"), Some("more.png")]);
+
+    let mut world = World::new();
+    map.apply(&mut world, vec![None, None]).unwrap();
+    world
+}
+
+#[test]
+fn tile_layer_is_split_per_tileset() {
+    let world = world();
+    assert_eq!(world.tilemaps.len(), 2);
+
+    let ground = &world.tilemaps[0];
+    assert_eq!(ground.tiles, vec![Some(0), Some(1), None, None, Some(1), None]);
+    assert!(!ground.is_solid(0, 0));
+    assert!(ground.is_solid(1, 0));
+    assert!(ground.is_solid(1, 1));
+
+    let more = &world.tilemaps[1];
+    assert_eq!(more.get(2, 1), Some(0));
+}
+
+#[test]
+fn map_size_becomes_boundary() {
+    let world = world();
+    assert_eq!(world.boundary.start.x, 0.);
+    assert_eq!(world.boundary.end.x, 48.);
+    assert_eq!(world.boundary.end.y, 32.);
+}
+
+#[test]
+fn objects_become_entities() {
+    let world = world();
+    let c = &world.components;
+    let player = world.player().unwrap();
+    assert_eq!(c.position.get(player), Some(&Coord { x: 8., y: 8., z: 0. }));
+
+    assert_eq!(world.entities.len(), 2);
+    assert_eq!(c.player.len(), 1);
+    let crate_ = world.entities.iter().copied().find(|&id| id != player).unwrap();
+    assert_eq!(c.position.get(crate_), Some(&Coord { x: 24., y: 4., z: 0. }));
+    let collider = c.collider.get(crate_).unwrap();
+    assert_eq!(collider.hitbox.start.x, -8.);
+    assert_eq!(collider.hitbox.end.y, 4.);
+    assert!(collider.pushable);
+    assert_eq!(collider.mass, 2.5);
+    let properties = c.properties.get(crate_).unwrap();
+    assert_eq!(properties["loot"], "coin");
+    assert_eq!(properties["name"], "crate");
+}
+
+#[test]
+fn external_tilesets_are_rejected() {
+    let map = r#"{ "width": 1, "height": 1, "tilewidth": 16, "tileheight": 16, "layers": [],
+        "tilesets": [ { "firstgid": 1, "source": "tiles.tsx" } ] }"#;
+    assert!(TiledMap::from_slice(map.as_bytes()).is_err());
+}
+
+#[test]
+fn oversized_layers_and_tilesets_are_rejected() {
+    let layer = format!(
+        r#"{{ "width": 1, "height": 1, "tilewidth": 16, "tileheight": 16,
+            "layers": [ {{ "type": "tilelayer", "name": "ground", "width": {}, "height": 2, "data": [1] }} ],
+            "tilesets": [ {{ "firstgid": 1, "image": "tiles.png", "tilecount": 1 }} ] }}"#,
+        usize::MAX,
+    );
+    let map = TiledMap::from_slice(layer.as_bytes()).unwrap();
+    assert!(map.apply(&mut World::new(), vec![None]).is_err());
+
+    let tileset = format!(
+        r#"{{ "width": 1, "height": 1, "tilewidth": 16, "tileheight": 16,
+            "layers": [ {{ "type": "tilelayer", "name": "ground", "width": 1, "height": 1, "data": [1] }} ],
+            "tilesets": [ {{ "firstgid": 1, "image": "tiles.png", "tilecount": {} }} ] }}"#,
+        u32::MAX,
+    );
+    let map = TiledMap::from_slice(tileset.as_bytes()).unwrap();
+    assert!(map.apply(&mut World::new(), vec![None]).is_err());
+}
+
+#[test]
+fn tileset_paths_follow_the_map() {
+    let mut map = TiledMap::from_slice(MAP.as_bytes()).unwrap();
+    map.relative_to("levels/one.json");
+    assert_eq!(map.tileset_images(), vec![Some("levels/tiles.png"), Some("levels/more.png")]);
+
+    let mut world = World::new();
+    map.apply(&mut world, vec![None, None]).unwrap();
+    assert_eq!(world.tilemaps[0].tileset_path.as_deref(), Some("levels/tiles.png"));
+}
+
+#[test]
+fn objects_can_move_on_their_own() {
+    let map = r#"{ "width": 4, "height": 4, "tilewidth": 16, "tileheight": 16, "layers": [
+        { "type": "objectgroup", "objects": [
+            { "name": "bat", "x": 0, "y": 0, "width": 16, "height": 16, "properties": [
+                { "name": "ai", "type": "string", "value": "chase" },
+                { "name": "radius", "type": "float", "value": 48 },
+                { "name": "speed", "type": "float", "value": 1.5 }
+            ] }
+        ] }
+    ] }"#;
+    let mut world = World::new();
+    TiledMap::from_slice(map.as_bytes()).unwrap().apply(&mut world, vec![]).unwrap();
+
+    let bat = world.components.ai.ids().next().unwrap();
+    assert_eq!(world.components.ai.get(bat), Some(&Ai::new(Behaviour::Chase { radius: 48. })));
+    assert_eq!(world.components.velocity.get(bat).unwrap().max.x, 1.5);
+}
+
+#[test]
+fn reloading_keeps_entity_state() {
+    let mut map = TiledMap::from_slice(MAP.as_bytes()).unwrap();
+    map.relative_to("level.json");
+    let mut world = World::new();
+    map.apply(&mut world, vec![None, None]).unwrap();
+
+    let player = world.player().unwrap();
+    let crate_ = world.components.properties.ids().next().unwrap();
+    world.components.position.insert(player, Coord { x: 30., y: 30., z: 0. });
+    world.components.position.insert(crate_, Coord { x: 40., y: 20., z: 0. });
+
+    let changed = MAP.replace("2.5", "4").replace("[1, 2, 0, 0, 2, 5]", "[1, 1, 1, 1, 1, 5]");
+    let mut map = TiledMap::from_slice(changed.as_bytes()).unwrap();
+    map.relative_to("level.json");
+    map.reload(&mut world, vec![None, None]).unwrap();
+
+    assert_eq!(world.entities.len(), 2);
+    assert_eq!(world.components.position.get(player), Some(&Coord { x: 30., y: 30., z: 0. }));
+    let (reloaded, properties) = world.components.properties.iter().next().unwrap();
+    assert_eq!(properties["name"], "crate");
+    assert_eq!(world.components.position.get(reloaded), Some(&Coord { x: 40., y: 20., z: 0. }));
+    assert_eq!(world.components.collider.get(reloaded).unwrap().mass, 4.);
+
+    assert_eq!(world.tilemaps.len(), 2);
+    assert_eq!(world.tilemaps[0].tiles, vec![Some(0); 5].into_iter().chain([None]).collect::<Vec<_>>());
+    assert_eq!(world.tilemaps[0].source.as_deref(), Some("level.json"));
+}
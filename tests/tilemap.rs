@@ -0,0 +1,78 @@
+//! Tile layers in `World`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::world::*;
+
+mod common;
+use common::pos;
+
+/// The player, standing at `pos` and not slowing down
+fn player(world: &mut World, pos: Coord) -> EntityId {
+    let id = world.player().unwrap();
+    world.components.position.insert(id, pos);
+    world.components.velocity.get_mut(id).unwrap().falloff = Coord::origin();
+    id
+}
+
+fn wall() -> Tilemap {
+    // A single solid column of tiles at x = 32..64
+    let mut map = Tilemap::new(4, 4, (32, 32));
+    map.origin = Coord { x: -64., y: -64., z: 0. };
+    map.solid = vec![false, true];
+    for row in 0..4 {
+        map.set(0, row, Some(0));
+        map.set(3, row, Some(1));
+    }
+    map
+}
+
+#[test]
+fn cells_are_clamped_to_the_map() {
+    let map = wall();
+    let (columns, rows) = map.cells_in(Region::default_boundary());
+    assert_eq!((columns, rows), (0..4, 0..4));
+
+    let (columns, rows) = map.cells_in(Region::default_hitbox());
+    assert_eq!((columns, rows), (1..3, 1..3));
+}
+
+#[test]
+fn solid_tiles_block_entities() {
+    let mut world = World::new();
+    world.tilemaps.push(wall());
+    let player = player(&mut world, Coord { x: 14., y: 0., z: 0. });
+    world.components.velocity.get_mut(player).unwrap().to.x = 3.;
+
+    world.tick();
+
+    assert_eq!(pos(&world, player).x, 16.);
+    assert_eq!(world.components.velocity.get(player).unwrap().to.x, 0.);
+}
+
+#[test]
+fn non_solid_tiles_do_not_block() {
+    let mut world = World::new();
+    world.tilemaps.push(wall());
+    let player = player(&mut world, Coord { x: -14., y: 0., z: 0. });
+    world.components.velocity.get_mut(player).unwrap().to.x = -3.;
+
+    world.tick();
+
+    assert_eq!(pos(&world, player).x, -17.);
+}
+
+#[test]
+fn sliding_along_a_wall_does_not_catch() {
+    let mut world = World::new();
+    world.tilemaps.push(wall());
+    let player = player(&mut world, Coord { x: 16., y: -40., z: 0. });
+
+    for _ in 0..20 {
+        world.components.velocity.get_mut(player).unwrap().to = Coord { x: 1., y: 3.5, z: 0. };
+        world.tick();
+    }
+
+    assert_eq!(pos(&world, player).x, 16.);
+    assert_eq!(pos(&world, player).y, 30.);
+}
@@ -0,0 +1,185 @@
+//! Widgets drawn in screen space over the world.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::{
+    assets::{self, AssetSource},
+    engine::{Engine, Pixel},
+    ui::*,
+    world::*,
+};
+use futures::executor::block_on;
+use image::{ImageOutputFormat, Rgba, RgbaImage};
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Engine drawing into a plain pixel buffer
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![(0, 0, 0); width * height] }
+    }
+
+    fn at(&self, x: usize, y: usize) -> Pixel {
+        self.pixels[y * self.width + x]
+    }
+}
+
+impl Engine for Canvas {
+    fn width(&self) -> usize {
+        self.width
+    }
+    fn height(&self) -> usize {
+        self.height
+    }
+    fn clear(&mut self) {
+        self.pixels = vec![(0, 0, 0); self.width * self.height];
+    }
+    fn set_at(&mut self, idx: usize, pixel: Pixel) {
+        self.pixels[idx] = pixel;
+    }
+    fn set_at_with_opacity(&mut self, idx: usize, pixel: Pixel, _opacity: f64) {
+        self.pixels[idx] = pixel;
+    }
+}
+
+#[test]
+fn anchors_follow_screen_size() {
+    let widget = Widget::panel((255, 0, 0)).at(Anchor::BottomRight, (-8., -8.)).sized(20., 10.);
+    assert_eq!(widget.place(widget.size, (640., 480.)), (612., 462.));
+    assert_eq!(widget.place(widget.size, (800., 600.)), (772., 582.));
+
+    let centered = Widget::panel((255, 0, 0)).at(Anchor::Center, (0., 0.)).sized(20., 10.);
+    assert_eq!(centered.place(centered.size, (640., 480.)), (310., 235.));
+    assert_eq!(Widget::label("x").place((6., 8.), (640., 480.)), (0., 0.));
+}
+
+#[test]
+fn labels_are_sized_to_their_text() {
+    let ui = Ui::new();
+    let fonts = BTreeMap::new();
+    let label = Widget::label("Hi").at(Anchor::TopRight, (0., 0.));
+    assert_eq!(ui.rect(&label, (100., 50.), &fonts), (88., 0., 12., 8.));
+
+    // Unknown fonts fall back to the builtin one
+    let label = Widget::new(WidgetKind::Label { text: "Hi".into(), font: Some("big.fnt".into()), scale: 2, color: (1, 1, 1) });
+    assert_eq!(ui.size_of(&label, &fonts), (24., 16.));
+}
+
+#[test]
+fn widgets_are_drawn_in_screen_space() {
+    let mut world = World::new();
+    world.scroll = (500., 500.);
+    world.ui.add(Widget::panel((0, 0, 255)).at(Anchor::BottomLeft, (0., 0.)).sized(10., 10.));
+    world.ui.add(Widget::bar(25., 100.).with_id("health").at(Anchor::TopRight, (0., 0.)).sized(40., 4.));
+    let mut canvas = Canvas::new(100, 50);
+
+    canvas.render_ui(&world);
+
+    assert_eq!(canvas.at(0, 49), (0, 0, 255));
+    assert_eq!(canvas.at(9, 40), (0, 0, 255));
+    assert_eq!(canvas.at(10, 40), (0, 0, 0));
+    assert_eq!(canvas.at(60, 0), (200, 32, 32));
+    assert_eq!(canvas.at(69, 3), (200, 32, 32));
+    assert_eq!(canvas.at(70, 0), (0, 0, 0));
+
+    world.ui.set_value("health", 100.);
+    let mut canvas = Canvas::new(200, 100);
+    canvas.render_ui(&world);
+    assert_eq!(canvas.at(160, 0), (200, 32, 32));
+    assert_eq!(canvas.at(199, 0), (200, 32, 32));
+    assert_eq!(canvas.at(0, 99), (0, 0, 255));
+}
+
+#[test]
+fn buttons_report_clicks() {
+    let mut ui = Ui::new();
+    let fonts = BTreeMap::new();
+    ui.add(Widget::button("Start").with_id("start").at(Anchor::Center, (0., 0.)).sized(60., 20.));
+    ui.add(Widget::button("Quit").with_id("quit").at(Anchor::Center, (0., 10.)).sized(60., 20.));
+
+    // Quit is on top where they overlap
+    assert_eq!(ui.click((100., 45.), (200., 100.), &fonts), Some("start".to_string()));
+    assert_eq!(ui.click((100., 55.), (200., 100.), &fonts), Some("quit".to_string()));
+    assert_eq!(ui.click((10., 10.), (200., 100.), &fonts), None);
+
+    ui.get_mut("quit").unwrap().visible = false;
+    assert_eq!(ui.click((100., 55.), (200., 100.), &fonts), Some("start".to_string()));
+}
+
+#[test]
+fn button_text_is_centered() {
+    let mut world = World::new();
+    world.ui.add(Widget::button("I").sized(11., 11.));
+    let mut canvas = Canvas::new(20, 20);
+
+    canvas.render_ui(&world);
+
+    // The stem of the builtin `I` is in the middle of its 5x7 cell
+    assert_eq!(canvas.at(5, 5), (255, 255, 255));
+    assert_eq!(canvas.at(1, 1), (64, 64, 96));
+}
+
+const LAYOUT: &str = r#"{
+    "widgets": [
+        { "type": "Panel", "color": [0, 0, 0], "border": [255, 255, 255], "size": [80, 20] },
+        { "type": "Icon", "image": "heart.png", "anchor": "TopRight", "offset": [-2, 2] },
+        { "type": "Label", "id": "score", "text": "0", "anchor": "Bottom" },
+        { "type": "Bar", "id": "health", "value": 3, "max": 4, "size": [40, 4], "visible": false }
+    ]
+}"#;
+
+#[test]
+fn layouts_are_assets() {
+    let heart = RgbaImage::from_pixel(3, 3, Rgba([255, 0, 0, 255]));
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgba8(heart).write_to(&mut png, ImageOutputFormat::Png).unwrap();
+    let index = br#"[ { "path": "ui/hud.json", "type": "Layout", "entity_id": null } ]"#;
+    let source = AssetSource::memory(vec![
+        ("index.json", index.to_vec()),
+        ("ui/hud.json", LAYOUT.as_bytes().to_vec()),
+        ("ui/heart.png", png),
+    ]);
+
+    let mut world = World::new();
+    world.ui.add(Widget::label("replaced"));
+    let assets = block_on(assets::load_assets(&source)).unwrap();
+    block_on(assets::import_assets(&source, assets, &mut world)).unwrap();
+
+    let ui = &world.ui;
+    assert_eq!(ui.widgets.len(), 4);
+    assert_eq!(ui.widgets[1].kind, WidgetKind::Icon { image: "ui/heart.png".into() });
+    assert_eq!(ui.widgets[1].anchor, Anchor::TopRight);
+    assert_eq!(ui.images["ui/heart.png"].dimensions(), (3, 3));
+    assert!(!ui.get("health").unwrap().visible);
+    assert_eq!(ui.get("score").unwrap().kind, WidgetKind::Label {
+        text: "0".into(),
+        font: None,
+        scale: 1,
+        color: (255, 255, 255),
+    });
+
+    world.ui.set_text("score", "100");
+    let mut canvas = Canvas::new(100, 50);
+    canvas.render_ui(&world);
+    assert_eq!(canvas.at(0, 0), (255, 255, 255));
+    assert_eq!(canvas.at(1, 1), (0, 0, 0));
+    assert_eq!(canvas.at(96, 2), (255, 0, 0));
+    assert_eq!(canvas.at(97, 5), (0, 0, 0));
+}
+
+#[test]
+fn layouts_round_trip() {
+    let layout = Layout::from_slice(LAYOUT.as_bytes()).unwrap();
+    let json = serde_json::to_vec(&layout).unwrap();
+    assert_eq!(Layout::from_slice(&json).unwrap(), layout);
+    assert!(Layout::from_slice(br#"{ "widgets": [ { "type": "Slider" } ] }"#).is_err());
+
+    let mut ui = Ui::new();
+    ui.images.insert("heart.png".into(), Arc::new(RgbaImage::new(5, 6)));
+    assert_eq!(ui.size_of(&layout.widgets[1], &BTreeMap::new()), (5., 6.));
+}
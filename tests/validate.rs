@@ -0,0 +1,85 @@
+//! Checking an asset directory before packing it.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::assets::validate::validate;
+use std::{fs, path::PathBuf};
+
+mod common;
+use common::{INDEX, MAP};
+
+fn assets_dir(name: &str) -> PathBuf {
+    common::assets_dir("validate", name)
+}
+
+#[test]
+fn valid_assets() {
+    let root = assets_dir("valid");
+    let report = validate(&root);
+    assert_eq!(report.problems, Vec::<String>::new());
+
+    let paths: Vec<&str> = report.sizes.iter().map(|(path, _)| path.as_str()).collect();
+    assert_eq!(paths, vec!["index.json", "player.png", "levels/one.json", "levels/tiles.png", "scripts/bounce.rhai"]);
+    let total: u64 = paths.iter().map(|path| fs::metadata(root.join(path)).unwrap().len()).sum();
+    assert_eq!(report.total_size(), total);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn missing_and_broken_files() {
+    let root = assets_dir("broken");
+    fs::remove_file(root.join("levels/tiles.png")).unwrap();
+    fs::write(root.join("player.png"), "not a png").unwrap();
+    fs::write(root.join("scripts/bounce.rhai"), "fn tick(world, id) {").unwrap();
+
+    let report = validate(&root);
+    assert!(!report.is_ok());
+    assert_eq!(report.problems.len(), 3);
+    assert!(report.problems[0].starts_with("player.png: "));
+    assert!(report.problems[1].starts_with("levels/tiles.png: "));
+    assert!(report.problems[2].starts_with("scripts/bounce.rhai: "));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn broken_references() {
+    let root = assets_dir("references");
+    let index = INDEX.replace(r#""entity_id": "crate""#, r#""entity_id": "barrel""#);
+    fs::write(root.join("index.json"), index).unwrap();
+    fs::write(root.join("levels/one.json"), MAP.replace("scripts/bounce.rhai", "scripts/gone.rhai")).unwrap();
+
+    let report = validate(&root);
+    assert_eq!(report.problems, vec![
+        "scripts/bounce.rhai: no level has an object called barrel".to_string(),
+        "levels/one.json: script scripts/gone.rhai is not in the index".to_string(),
+    ]);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn missing_index() {
+    let root = assets_dir("index");
+    fs::remove_file(root.join("index.json")).unwrap();
+    let report = validate(&root);
+    assert_eq!(report.problems.len(), 1);
+    assert!(report.problems[0].starts_with("index.json: "));
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn layout_references() {
+    let root = assets_dir("layout");
+    let index = INDEX.replace("\n]", ",\n    { \"path\": \"ui/hud.json\", \"type\": \"Layout\", \"entity_id\": null }\n]");
+    fs::write(root.join("index.json"), index).unwrap();
+    fs::create_dir_all(root.join("ui")).unwrap();
+    fs::write(root.join("ui/hud.json"), r#"{ "widgets": [
+        { "type": "Icon", "image": "heart.png" },
+        { "type": "Label", "text": "0", "font": "fonts/big.fnt" }
+    ] }"#).unwrap();
+
+    let report = validate(&root);
+    assert_eq!(report.problems.len(), 2);
+    assert!(report.problems[0].starts_with("ui/heart.png: "));
+    assert_eq!(report.problems[1], "ui/hud.json: font fonts/big.fnt is not in the index");
+    fs::remove_dir_all(root).unwrap();
+}
@@ -0,0 +1,81 @@
+//! Damping behaviour of `Velocity`.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::world::{Coord, Damping, Velocity};
+use proptest::prelude::*;
+
+fn velocity(to: Coord, falloff: Coord, damping: Damping) -> Velocity {
+    Velocity {
+        to,
+        falloff,
+        damping,
+        ..Velocity::new()
+    }
+}
+
+fn damping() -> impl Strategy<Value = Damping> {
+    prop_oneof![Just(Damping::Linear), Just(Damping::Exponential)]
+}
+
+#[test]
+fn small_velocity_stops_at_zero() {
+    let mut vel = velocity(
+        Coord { x: 0.1, y: -0.1, z: 0. },
+        Coord::default_vel_falloff(),
+        Damping::Linear,
+    );
+    vel.do_falloff();
+    assert_eq!(vel.to.x, 0.);
+    assert_eq!(vel.to.y, 0.);
+
+    vel.do_falloff();
+    assert_eq!(vel.to.x, 0.);
+    assert_eq!(vel.to.y, 0.);
+}
+
+#[test]
+fn exponential_scales_with_time_step() {
+    let falloff = Coord { x: 0.5, y: 0.5, z: 0. };
+    let mut stepped = velocity(Coord { x: 2., y: 0., z: 0. }, falloff, Damping::Exponential);
+    stepped.damp(1.);
+    stepped.damp(1.);
+
+    let mut once = velocity(Coord { x: 2., y: 0., z: 0. }, falloff, Damping::Exponential);
+    once.damp(2.);
+
+    assert!((stepped.to.x - 0.5).abs() < 1e-9);
+    assert!((once.to.x - stepped.to.x).abs() < 1e-9);
+}
+
+proptest! {
+    #[test]
+    fn damping_never_changes_sign(
+        x in -10f64..10.,
+        y in -10f64..10.,
+        fx in -2f64..2.,
+        fy in -2f64..2.,
+        dt in 0f64..10.,
+        steps in 1usize..50,
+        damping in damping(),
+    ) {
+        let mut vel = velocity(Coord { x, y, z: 0. }, Coord { x: fx, y: fy, z: 0. }, damping);
+        for _ in 0..steps {
+            vel.damp(dt);
+            prop_assert!(vel.to.x * x >= 0.);
+            prop_assert!(vel.to.y * y >= 0.);
+        }
+    }
+
+    #[test]
+    fn damping_never_speeds_up(
+        x in -10f64..10.,
+        fx in -2f64..2.,
+        dt in 0f64..10.,
+        damping in damping(),
+    ) {
+        let mut vel = velocity(Coord { x, y: 0., z: 0. }, Coord { x: fx, y: 0., z: 0. }, damping);
+        vel.damp(dt);
+        prop_assert!(vel.to.x.abs() <= x.abs());
+    }
+}
@@ -0,0 +1,54 @@
+//! Noticing changed asset files on native.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use canvasgame_rust::assets::{watch::AssetWatcher, Asset};
+use std::{fs, path::PathBuf};
+
+mod common;
+use common::INDEX;
+
+fn assets_dir(name: &str) -> PathBuf {
+    common::assets_dir("watch", name)
+}
+
+fn index() -> Vec<Asset> {
+    serde_json::from_str(INDEX).unwrap()
+}
+
+#[test]
+fn nothing_changed() {
+    let root = assets_dir("nothing");
+    let mut watcher = AssetWatcher::with_root(&root, index());
+    assert!(watcher.changed().unwrap().is_empty());
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn changed_entries_are_reloaded() {
+    let root = assets_dir("entries");
+    let mut watcher = AssetWatcher::with_root(&root, index());
+
+    fs::write(root.join("player.png"), "bb").unwrap();
+    assert_eq!(watcher.changed().unwrap(), index()[..1].to_vec());
+    assert!(watcher.changed().unwrap().is_empty());
+
+    // Tilesets are not in the index, but belong to a map
+    fs::write(root.join("levels/tiles.png"), "bb").unwrap();
+    assert_eq!(watcher.changed().unwrap(), index()[1..2].to_vec());
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn changed_index_reloads_everything() {
+    let root = assets_dir("index");
+    let mut watcher = AssetWatcher::with_root(&root, index());
+
+    let shorter = r#"[ { "path": "player.png", "type": "Texture", "entity_id": "player" } ]"#;
+    fs::write(root.join("index.json"), shorter).unwrap();
+    assert_eq!(watcher.changed().unwrap(), index()[..1].to_vec());
+
+    fs::write(root.join("index.json"), "[ broken").unwrap();
+    assert!(watcher.changed().is_err());
+    fs::remove_dir_all(root).unwrap();
+}
@@ -11,3 +11,11 @@ wasm_bindgen_test_configure!(run_in_browser);
 fn pass() {
     assert_eq!(1 + 1, 2);
 }
+
+#[wasm_bindgen_test]
+async fn missing_assets_are_errors() {
+    use canvasgame_rust::assets::AssetSource;
+
+    let source = AssetSource::fetch("/no-such-directory");
+    assert!(source.read("index.json").await.is_err());
+}
@@ -1,7 +1,10 @@
-import { load_asset_file, load_asset_index } from "./assets";
-window.load_asset_file_inner = load_asset_file;
+import { load_asset_index } from "./assets";
 window.load_asset_index_inner = load_asset_index;
 
+import { save_state, load_state } from "./storage";
+window.save_state_inner = save_state;
+window.load_state_inner = load_state;
+
 import * as wasm from "canvasgame-rust";
 
 
@@ -26,4 +29,25 @@ window.addEventListener('keyup', e => {
     wasm.key_up(e);
 })
 
+window.addEventListener('blur', () => {
+    wasm.focus_lost();
+});
+
+window.addEventListener('focus', () => {
+    wasm.focus_gained();
+});
+
+document.addEventListener('visibilitychange', () => {
+    if (document.hidden) {
+        wasm.focus_lost();
+    } else {
+        wasm.focus_gained();
+    }
+});
+
+canvas.addEventListener('mousedown', e => {
+    let scale = window.devicePixelRatio;
+    wasm.pointer_down(e.offsetX * scale, e.offsetY * scale);
+});
+
 wasm.run();
\ No newline at end of file
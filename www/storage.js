@@ -0,0 +1,7 @@
+export function save_state(key, data) {
+    window.localStorage.setItem(key, data);
+}
+
+export function load_state(key) {
+    return window.localStorage.getItem(key);
+}
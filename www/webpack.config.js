@@ -17,6 +17,11 @@ module.exports = {
         {
           from: '../assets/**',
           to: 'assets/'
+        },
+        {
+          from: '../assets.pack',
+          to: '.',
+          noErrorOnMissing: true
         }
       ]
     })